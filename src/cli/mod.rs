@@ -1,7 +1,7 @@
 use super::problemspec::spec::*;
 use super::runner::*;
 use super::testspec::spec::*;
-use clap::{AppSettings, Parser};
+use clap::Parser;
 
 #[derive(Parser)]
 #[clap(
@@ -29,6 +29,87 @@ struct GenerateCommand {
 
     #[clap(long, default_value = "0")]
     seed: u64,
+
+    /// Check every test case and report all constraint violations at the end. Nothing is
+    /// written when any test fails
+    #[clap(long)]
+    keep_going: bool,
+
+    /// Layout of the output folder: flat, polygon, kattis, cms or tcframe
+    #[clap(long, default_value = "flat")]
+    format: Format,
+
+    /// Time limit in milliseconds, 1000 unless the spec sets one
+    #[clap(long)]
+    time_limit: Option<u64>,
+
+    /// Memory limit in megabytes, for package formats, 256 unless the spec sets one
+    #[clap(long)]
+    memory_limit: Option<u64>,
+
+    /// Checker for package formats: exact, tokens, float, float:<error> or the source of a
+    /// custom checker, compiled beforehand for cms
+    #[clap(long)]
+    checker: Option<CheckerKind>,
+
+    /// Also pack the output folder into a zip file
+    #[clap(long)]
+    zip: bool,
+
+    /// Number of solution processes to run at once. Runtimes are measured under contention when
+    /// above 1, so use 1 for reliable time limit warnings
+    #[clap(short, long, default_value = "1")]
+    jobs: usize,
+
+    /// Run the solution on every test instead of reusing cached outputs
+    #[clap(long)]
+    no_cache: bool,
+
+    /// Replace the output folder even if it does not look like testgen output
+    #[clap(long)]
+    force: bool,
+
+    /// Problem identifier for file names and packages, defaulting to the spec's slug, then to
+    /// the output folder name
+    #[clap(long)]
+    slug: Option<String>,
+
+    /// File name template for the flat and tcframe formats, e.g. "{slug}_{subtask}_{case:02}"
+    #[clap(long)]
+    name_template: Option<String>,
+
+    /// Extension of input files for the flat and tcframe formats, "in" by default
+    #[clap(long)]
+    input_extension: Option<String>,
+
+    /// Extension of output files for the flat and tcframe formats, "out" by default
+    #[clap(long)]
+    output_extension: Option<String>,
+}
+
+impl From<GenerateCommand> for GenerateConfig {
+    fn from(g: GenerateCommand) -> Self {
+        GenerateConfig {
+            output: g.output,
+            solution: g.solution,
+            seed: g.seed,
+            keep_going: g.keep_going,
+            format: g.format,
+            time_limit: g.time_limit,
+            memory_limit: g.memory_limit,
+            checker: g.checker,
+            zip: g.zip,
+            jobs: g.jobs,
+            no_cache: g.no_cache,
+            force: g.force,
+            slug: g.slug,
+            naming: NamingConfig {
+                template: g.name_template,
+                input_extension: g.input_extension,
+                output_extension: g.output_extension,
+            },
+        }
+    }
 }
 
 #[derive(Parser)]
@@ -47,14 +128,12 @@ where
     let opts: Opts = Opts::parse();
 
     match opts.subcmd {
-        SubCommand::Generate(g) => {
-            match run_singletask::<T>(&g.output, g.solution.as_ref().map(String::as_str), g.seed) {
-                Ok(_) => {}
-                Err(err) => {
-                    println!("{}", err);
-                }
+        SubCommand::Generate(g) => match run_singletask::<T>(&g.into()) {
+            Ok(_) => {}
+            Err(err) => {
+                println!("{}", err);
             }
-        }
+        },
         SubCommand::Grade(_) => {
             todo!()
         }
@@ -67,14 +146,12 @@ where
     let opts: Opts = Opts::parse();
 
     match opts.subcmd {
-        SubCommand::Generate(g) => {
-            match run_multitask::<T>(&g.output, g.solution.as_ref().map(String::as_str), g.seed) {
-                Ok(_) => {}
-                Err(err) => {
-                    println!("{}", err);
-                }
+        SubCommand::Generate(g) => match run_multitask::<T>(&g.into()) {
+            Ok(_) => {}
+            Err(err) => {
+                println!("{}", err);
             }
-        }
+        },
         SubCommand::Grade(_) => {
             todo!()
         }
@@ -1,3 +1,6 @@
+// Lets `#[derive(ProblemSpec)]` refer to `::testgen` from inside this crate too.
+extern crate self as testgen;
+
 pub mod cli;
 pub mod problemspec;
 pub mod runner;
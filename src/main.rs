@@ -1,34 +1,21 @@
 use testgen::cli::run_multi;
 use testgen::problemspec::spec::*;
-use testgen::testspec::random::Random;
 use testgen::testspec::spec::*;
-use testgen::{CONS, LINE, LS};
+use testgen::CONS;
 
+#[derive(ProblemSpec)]
+#[multiple_testcases(t < 10)]
+#[output_prefix("Case #{}: ")]
 struct Spec {
+    #[line(0)]
+    #[constraint(a > 0)]
     a: i64,
+    #[line(0)]
+    #[constraint(b > 0)]
     b: i64,
+    #[output]
     sum: Option<i64>,
 }
-impl ProblemSpec<Spec> for Spec {
-    fn input_format(&self) -> IOFormat {
-        vec![LINE!(LS!(self.a), LS!(self.b))]
-    }
-
-    fn output_format(&self) -> IOFormat {
-        vec![LINE!(LS!(self.sum.unwrap()))]
-    }
-
-    fn multiple_test_case_config() -> Option<MultipleTestcaseConfig> {
-        Some(MultipleTestcaseConfig {
-            constraints: |t| CONS!(t < 10),
-            output_prefix: Some("Case #{}: ".to_string()),
-        })
-    }
-
-    fn constraints(&self) -> Result<(), ConstraintsError> {
-        CONS!(self.a > 0, self.b > 0)
-    }
-}
 
 impl MultitaskProblemSpec<Spec> for Spec {
     fn subtask_1() -> Option<SubtaskConfig<Spec>> {
@@ -47,16 +34,15 @@ impl MultitaskProblemSpec<Spec> for Spec {
 }
 
 impl SingletaskTestSpec<Spec> for Spec {
-    fn test_cases(random: &mut Random) -> Vec<Spec> {
-        let mut result = Vec::new();
+    fn test_cases(mut tests: TestCases<Spec>) -> TestCases<Spec> {
         for _ in 0..9 {
-            result.push(Spec {
+            tests.add(|random| Spec {
                 a: random.next_range(1, 10),
                 b: random.next_range(1, 10),
                 sum: None,
             });
         }
-        result
+        tests
     }
 
     fn sample_test_cases() -> Vec<Spec> {
@@ -75,27 +61,25 @@ impl SingletaskTestSpec<Spec> for Spec {
     }
 }
 impl MultitaskTestSpec<Spec> for Spec {
-    fn test_cases_subtask_1(random: &mut Random) -> Option<Vec<Spec>> {
-        let mut result = Vec::new();
+    fn test_cases_subtask_1(mut tests: TestCases<Spec>) -> Option<TestCases<Spec>> {
         for _ in 0..9 {
-            result.push(Spec {
+            tests.add(|random| Spec {
                 a: random.next_range(1, 10),
                 b: random.next_range(1, 10),
                 sum: None,
             });
         }
-        Some(result)
+        Some(tests)
     }
-    fn test_cases_subtask_2(random: &mut Random) -> Option<Vec<Spec>> {
-        let mut result = Vec::new();
+    fn test_cases_subtask_2(mut tests: TestCases<Spec>) -> Option<TestCases<Spec>> {
         for _ in 0..9 {
-            result.push(Spec {
+            tests.add(|random| Spec {
                 a: random.next_range(1, 100),
                 b: random.next_range(1, 100),
                 sum: None,
             });
         }
-        Some(result)
+        Some(tests)
     }
 
     fn sample_test_cases() -> Vec<Spec> {
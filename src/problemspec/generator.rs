@@ -8,9 +8,7 @@ impl Generator for IOFormat {
     fn generate(&self) -> Option<String> {
         Some(
             self.iter()
-                .map(|e| e.generate())
-                .filter(|e| e.is_some())
-                .map(|e| e.unwrap())
+                .filter_map(|e| e.generate())
                 .collect::<Vec<String>>()
                 .join("\n"),
         )
@@ -32,11 +30,11 @@ impl Generator for IOElement {
                     for (pos, line) in lines.iter().enumerate() {
                         result.push_str(line[i].generate().unwrap().as_str());
                         if pos != lines.len() - 1 {
-                            result.push_str(" ");
+                            result.push(' ');
                         }
                     }
                     if i != *size - 1 {
-                        result.push_str("\n");
+                        result.push('\n');
                     }
                 }
                 if result.is_empty() {
@@ -51,7 +49,7 @@ impl Generator for IOElement {
                     for (pos, line) in lines.iter().enumerate() {
                         result.push_str(line[i].generate().unwrap().as_str());
                         if pos != lines.len() - 1 {
-                            result.push_str(" ");
+                            result.push(' ');
                         }
                     }
                     if i != lines[0].len() - 1 {
@@ -68,7 +66,7 @@ impl Generator for IOElement {
             IOElement::EmptyLine => Some("".to_string()),
             IOElement::RawLinesBounded(lines, size) => Some(
                 lines
-                    .into_iter()
+                    .iter()
                     .take(*size)
                     .map(|line| line.to_string())
                     .collect::<Vec<String>>()
@@ -77,6 +75,7 @@ impl Generator for IOElement {
             IOElement::RawLinesUnbounded(lines) => Some(lines.join("\n")),
             IOElement::Grid(grid, height, width) => {
                 let mut result = String::new();
+                #[allow(clippy::needless_range_loop)]
                 for i in 0..*height {
                     for j in 0..*width {
                         result.push_str(grid[i][j].generate().unwrap().as_str());
@@ -132,6 +131,7 @@ impl Generator for LineElement {
 }
 
 #[cfg(test)]
+#[allow(clippy::useless_vec, clippy::approx_constant)]
 mod tests {
     use crate::{EMPTY_LINE, LINE, LINES, LS, LV, RAW_LINE, RAW_LINES, V};
 
@@ -1,3 +1,4 @@
 pub mod converter;
 pub mod generator;
+pub mod predicates;
 pub mod spec;
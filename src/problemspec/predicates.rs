@@ -0,0 +1,317 @@
+use std::{collections::HashMap, convert::TryInto, fmt::Debug, hash::Hash};
+
+use super::spec::ConstraintsError;
+
+fn fail(message: String) -> Result<(), ConstraintsError> {
+    Err(ConstraintsError::new(message))
+}
+
+pub fn all_in_range<T>(v: &[T], from: T, to: T) -> Result<(), ConstraintsError>
+where
+    T: PartialOrd + Debug,
+{
+    match v
+        .iter()
+        .enumerate()
+        .find(|(_, x)| !(from <= **x && **x <= to))
+    {
+        None => Ok(()),
+        Some((i, x)) => fail(format!(
+            "all elements in [{:?}, {:?}], found [{}] = {:?}",
+            from, to, i, x
+        )),
+    }
+}
+
+pub fn all_distinct<T>(v: &[T]) -> Result<(), ConstraintsError>
+where
+    T: Eq + Hash + Debug,
+{
+    let mut seen = HashMap::new();
+    for (i, x) in v.iter().enumerate() {
+        if let Some(j) = seen.insert(x, i) {
+            return fail(format!(
+                "all elements distinct, found [{}] = [{}] = {:?}",
+                j, i, x
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Checks that `v` contains every number from 1 to `v.len()` exactly once.
+pub fn is_permutation<T>(v: &[T]) -> Result<(), ConstraintsError>
+where
+    T: Copy + Debug + TryInto<usize>,
+{
+    let n = v.len();
+    let mut seen = vec![None; n + 1];
+    for (i, x) in v.iter().enumerate() {
+        match (*x).try_into() {
+            Ok(value) if 1 <= value && value <= n => {
+                if let Some(j) = seen[value] {
+                    return fail(format!(
+                        "a permutation of 1..={}, found [{}] = [{}] = {:?}",
+                        n, j, i, x
+                    ));
+                }
+                seen[value] = Some(i);
+            }
+            _ => {
+                return fail(format!(
+                    "a permutation of 1..={}, found [{}] = {:?}",
+                    n, i, x
+                ))
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Checks that `v` is sorted in non-decreasing order.
+pub fn is_sorted<T>(v: &[T]) -> Result<(), ConstraintsError>
+where
+    T: PartialOrd + Debug,
+{
+    match v.windows(2).position(|w| w[0] > w[1]) {
+        None => Ok(()),
+        Some(i) => fail(format!(
+            "sorted, found [{}] = {:?} > [{}] = {:?}",
+            i,
+            v[i],
+            i + 1,
+            v[i + 1]
+        )),
+    }
+}
+
+pub fn over_alphabet(s: &str, alphabet: &str) -> Result<(), ConstraintsError> {
+    match s.chars().enumerate().find(|(_, c)| !alphabet.contains(*c)) {
+        None => Ok(()),
+        Some((i, c)) => fail(format!(
+            "characters in {:?}, found [{}] = {:?}",
+            alphabet, i, c
+        )),
+    }
+}
+
+pub fn no_self_loops<T>(edges: &[(T, T)]) -> Result<(), ConstraintsError>
+where
+    T: PartialEq + Debug,
+{
+    match edges.iter().enumerate().find(|(_, (u, v))| u == v) {
+        None => Ok(()),
+        Some((i, edge)) => fail(format!("no self loops, found edge [{}] = {:?}", i, edge)),
+    }
+}
+
+/// Checks that no two edges connect the same pair of nodes, in either direction.
+pub fn no_multi_edges<T>(edges: &[(T, T)]) -> Result<(), ConstraintsError>
+where
+    T: Ord + Hash + Debug,
+{
+    let mut seen = HashMap::new();
+    for (i, (u, v)) in edges.iter().enumerate() {
+        let key = if u <= v { (u, v) } else { (v, u) };
+        if let Some(j) = seen.insert(key, i) {
+            return fail(format!(
+                "no multiple edges, found edges [{}] = {:?} and [{}] = {:?}",
+                j, edges[j], i, edges[i]
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Converts the 1-based edge list into 0-based node pairs, checking every node is within `1..=n`.
+fn nodes<T>(n: usize, edges: &[(T, T)]) -> Result<Vec<(usize, usize)>, ConstraintsError>
+where
+    T: Copy + Debug + TryInto<usize>,
+{
+    let node = |x: T| match x.try_into() {
+        Ok(x) if 1 <= x && x <= n => Some(x - 1),
+        _ => None,
+    };
+    let mut result = Vec::with_capacity(edges.len());
+    for (i, (u, v)) in edges.iter().enumerate() {
+        match (node(*u), node(*v)) {
+            (Some(u), Some(v)) => result.push((u, v)),
+            _ => {
+                return Err(ConstraintsError::new(format!(
+                    "nodes in [1, {}], found edge [{}] = {:?}",
+                    n,
+                    i,
+                    (u, v)
+                )))
+            }
+        }
+    }
+    Ok(result)
+}
+
+/// Checks that the undirected graph with nodes `1..=n` is connected.
+pub fn is_connected<T>(n: usize, edges: &[(T, T)]) -> Result<(), ConstraintsError>
+where
+    T: Copy + Debug + TryInto<usize>,
+{
+    let edges = nodes(n, edges)?;
+    let mut adjacency = vec![Vec::new(); n];
+    for (u, v) in edges {
+        adjacency[u].push(v);
+        adjacency[v].push(u);
+    }
+
+    let mut visited = vec![false; n];
+    let mut stack = Vec::new();
+    if n > 0 {
+        visited[0] = true;
+        stack.push(0);
+    }
+    while let Some(u) = stack.pop() {
+        for &v in &adjacency[u] {
+            if !visited[v] {
+                visited[v] = true;
+                stack.push(v);
+            }
+        }
+    }
+
+    match visited.iter().position(|visited| !visited) {
+        None => Ok(()),
+        Some(u) => fail(format!(
+            "connected graph, found node {} unreachable from node 1",
+            u + 1
+        )),
+    }
+}
+
+/// Checks that `edges` form a tree over the nodes `1..=n`.
+pub fn is_tree<T>(n: usize, edges: &[(T, T)]) -> Result<(), ConstraintsError>
+where
+    T: Copy + Debug + TryInto<usize>,
+{
+    if edges.len() + 1 != n {
+        return fail(format!(
+            "a tree with {} nodes has {} edges, found {}",
+            n,
+            n.saturating_sub(1),
+            edges.len()
+        ));
+    }
+    is_connected(n, edges)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CONS;
+
+    #[test]
+    fn test_all_in_range() {
+        assert!(all_in_range(&[1, 5, 10], 1, 10).is_ok());
+        assert!(all_in_range(&[] as &[i32], 1, 10).is_ok());
+        assert_eq!(
+            all_in_range(&[1, 5, 11], 1, 10).unwrap_err().messages,
+            vec!["all elements in [1, 10], found [2] = 11".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_all_distinct() {
+        assert!(all_distinct(&[1, 2, 3]).is_ok());
+        assert_eq!(
+            all_distinct(&["a", "b", "a"]).unwrap_err().messages,
+            vec!["all elements distinct, found [0] = [2] = \"a\"".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_is_permutation() {
+        assert!(is_permutation(&[3, 1, 2]).is_ok());
+        assert!(is_permutation(&[] as &[u32]).is_ok());
+        assert_eq!(
+            is_permutation(&[3, 1, 3]).unwrap_err().messages,
+            vec!["a permutation of 1..=3, found [0] = [2] = 3".to_string()]
+        );
+        assert_eq!(
+            is_permutation(&[0i64, 1, 2]).unwrap_err().messages,
+            vec!["a permutation of 1..=3, found [0] = 0".to_string()]
+        );
+        assert!(is_permutation(&[-1i64]).is_err());
+        assert!(is_permutation(&[4, 1, 2]).is_err());
+    }
+
+    #[test]
+    fn test_is_sorted() {
+        assert!(is_sorted(&[1, 1, 2]).is_ok());
+        assert_eq!(
+            is_sorted(&[1, 3, 2]).unwrap_err().messages,
+            vec!["sorted, found [1] = 3 > [2] = 2".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_over_alphabet() {
+        assert!(over_alphabet("abba", "ab").is_ok());
+        assert_eq!(
+            over_alphabet("abca", "ab").unwrap_err().messages,
+            vec!["characters in \"ab\", found [2] = 'c'".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_no_self_loops() {
+        assert!(no_self_loops(&[(1, 2), (2, 3)]).is_ok());
+        assert_eq!(
+            no_self_loops(&[(1, 2), (3, 3)]).unwrap_err().messages,
+            vec!["no self loops, found edge [1] = (3, 3)".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_no_multi_edges() {
+        assert!(no_multi_edges(&[(1, 2), (2, 3), (1, 3)]).is_ok());
+        assert_eq!(
+            no_multi_edges(&[(1, 2), (2, 3), (2, 1)])
+                .unwrap_err()
+                .messages,
+            vec!["no multiple edges, found edges [0] = (1, 2) and [2] = (2, 1)".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_is_connected() {
+        assert!(is_connected(3, &[(1, 2), (3, 2)]).is_ok());
+        assert!(is_connected(1, &[] as &[(usize, usize)]).is_ok());
+        assert_eq!(
+            is_connected(4, &[(1, 2), (3, 4)]).unwrap_err().messages,
+            vec!["connected graph, found node 3 unreachable from node 1".to_string()]
+        );
+        assert_eq!(
+            is_connected(2, &[(1, 3)]).unwrap_err().messages,
+            vec!["nodes in [1, 2], found edge [0] = (1, 3)".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_is_tree() {
+        assert!(is_tree(4, &[(1, 2), (1, 3), (3, 4)]).is_ok());
+        assert!(is_tree(1, &[] as &[(u32, u32)]).is_ok());
+        assert_eq!(
+            is_tree(3, &[(1, 2), (2, 3), (3, 1)]).unwrap_err().messages,
+            vec!["a tree with 3 nodes has 2 edges, found 3".to_string()]
+        );
+        assert!(is_tree(4, &[(1, 2), (2, 1), (3, 4)]).is_err());
+    }
+
+    #[test]
+    fn test_predicates_compose_with_cons() {
+        let v = vec![1, 2, 2];
+        assert_eq!(
+            CONS!(v.len() == 3, all_distinct(&v), is_sorted(&v))
+                .unwrap_err()
+                .messages,
+            vec!["all elements distinct, found [1] = [2] = 2".to_string()]
+        );
+    }
+}
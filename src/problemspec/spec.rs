@@ -1,19 +1,93 @@
+use std::str::FromStr;
+
 use thiserror::Error;
 
+#[cfg(feature = "derive")]
+pub use testgen_derive::ProblemSpec;
+
 #[derive(Debug, Error)]
 #[error("Expected: {messages:?}")]
 pub struct ConstraintsError {
     pub messages: Vec<String>,
 }
 
+impl ConstraintsError {
+    pub fn new(message: String) -> Self {
+        ConstraintsError {
+            messages: vec![message],
+        }
+    }
+
+    /// Combines the failures of several checks into one error.
+    pub fn collect<I>(results: I) -> Result<(), Self>
+    where
+        I: IntoIterator<Item = Result<(), Self>>,
+    {
+        let messages: Vec<String> = results
+            .into_iter()
+            .filter_map(Result::err)
+            .flat_map(|error| error.messages)
+            .collect();
+        if messages.is_empty() {
+            Ok(())
+        } else {
+            Err(ConstraintsError { messages })
+        }
+    }
+
+    /// Prefixes every message, e.g. with the subtask and test case that failed.
+    pub fn with_context(self, context: &str) -> Self {
+        ConstraintsError {
+            messages: self
+                .messages
+                .into_iter()
+                .map(|message| format!("{}: {}", context, message))
+                .collect(),
+        }
+    }
+}
+
+/// Anything `CONS!` accepts: a `bool`, or the result of another constraint check such as
+/// `IN_RANGE!` or `ALL!`.
+pub trait Constraint {
+    fn check(self, expression: &str) -> Result<(), ConstraintsError>;
+}
+
+impl Constraint for bool {
+    fn check(self, expression: &str) -> Result<(), ConstraintsError> {
+        if self {
+            Ok(())
+        } else {
+            Err(ConstraintsError::new(expression.to_string()))
+        }
+    }
+}
+
+impl Constraint for Result<(), ConstraintsError> {
+    fn check(self, _expression: &str) -> Result<(), ConstraintsError> {
+        self
+    }
+}
+
 pub trait ProblemSpec<T> {
     fn input_format(&self) -> IOFormat;
     // TODO: check output format
     fn output_format(&self) -> IOFormat;
     fn constraints(&self) -> Result<(), ConstraintsError>;
+    /// Constraints over all test cases written to one file, e.g. a limit on the sum of N.
+    /// Without `multiple_test_case_config` every file holds a single test case.
+    fn file_constraints(_specs: &[T]) -> Result<(), ConstraintsError> {
+        Ok(())
+    }
     fn multiple_test_case_config() -> Option<MultipleTestcaseConfig> {
         None
     }
+    fn naming_config() -> NamingConfig {
+        NamingConfig::default()
+    }
+    fn metadata() -> ProblemMetadata {
+        ProblemMetadata::default()
+    }
 }
 
 pub trait MultitaskProblemSpec<T> {
@@ -35,6 +109,80 @@ pub struct MultipleTestcaseConfig {
     pub output_prefix: Option<String>,
 }
 
+/// How the flat and tcframe layouts name test files. Unset fields keep the layout's defaults.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NamingConfig {
+    /// File name without the extension. `{slug}` is the problem slug, `{subtask}` the subtask
+    /// number from 1 (1 without subtasks, `sample` for samples) and `{case}` the test number
+    /// from 1 within its subtask. Numbers can be zero-padded, as in `{case:02}`.
+    pub template: Option<String>,
+    pub input_extension: Option<String>,
+    pub output_extension: Option<String>,
+}
+
+impl NamingConfig {
+    /// Fills the fields unset in `self` from `other`.
+    pub fn or(self, other: NamingConfig) -> NamingConfig {
+        NamingConfig {
+            template: self.template.or(other.template),
+            input_extension: self.input_extension.or(other.input_extension),
+            output_extension: self.output_extension.or(other.output_extension),
+        }
+    }
+}
+
+/// Identity and limits of a problem. Command-line options take precedence over these.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ProblemMetadata {
+    /// Short identifier for file names and packages, defaulting to the output folder name.
+    pub slug: Option<String>,
+    /// Display name, defaulting to the slug.
+    pub title: Option<String>,
+    /// In milliseconds.
+    pub time_limit: Option<u64>,
+    /// In megabytes.
+    pub memory_limit: Option<u64>,
+    /// Must match the sum of the subtask scores when there are subtasks.
+    pub total_score: Option<u32>,
+    pub checker: Option<CheckerKind>,
+}
+
+/// How contestant outputs are compared with the expected ones.
+#[derive(Clone, Debug, PartialEq)]
+pub enum CheckerKind {
+    /// Lines must match exactly.
+    Exact,
+    /// Whitespace-separated tokens must match.
+    Tokens,
+    /// Tokens must match, numbers up to the given absolute or relative error.
+    Float(f64),
+    /// A checker built from the given source file.
+    Custom(String),
+}
+
+impl FromStr for CheckerKind {
+    type Err = String;
+
+    /// `exact`, `tokens`, `float` (with an error of 1e-6) or `float:<error>`. Anything else is
+    /// taken as the path to a custom checker.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "exact" => Ok(CheckerKind::Exact),
+            "tokens" => Ok(CheckerKind::Tokens),
+            "float" => Ok(CheckerKind::Float(1e-6)),
+            _ => match s.strip_prefix("float:") {
+                Some(error) => error
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|error| error.is_finite())
+                    .map(CheckerKind::Float)
+                    .ok_or_else(|| format!("invalid float checker error {:?}", error)),
+                None => Ok(CheckerKind::Custom(s.to_string())),
+            },
+        }
+    }
+}
+
 pub struct SubtaskConfig<T> {
     pub score: u8,
     pub constraints: fn(&T) -> Result<(), ConstraintsError>,
@@ -57,13 +205,7 @@ pub enum IOElement {
 #[macro_export]
 macro_rules! LINE {
     ($($x : expr), + $(,) ?) => {
-        {
-            let mut contents = Vec::new();
-            $(
-                contents.push($x);
-            )*
-            IOElement::Line(contents)
-        }
+        IOElement::Line(vec![$($x), +])
     };
 }
 
@@ -143,23 +285,109 @@ macro_rules! CONS {
         {
             let mut errors = Vec::new();
             $(
-                if !($x) {
-                    errors.push(String::from(stringify!($x)));
+                if let Err(error) = $crate::problemspec::spec::Constraint::check($x, stringify!($x)) {
+                    errors.extend(error.messages);
                 }
             )*
             if errors.is_empty() {
                 Ok(())
             } else {
-                Err(ConstraintsError { messages: errors })
+                Err($crate::problemspec::spec::ConstraintsError { messages: errors })
             }
         }
     };
 }
 
+#[macro_export]
+macro_rules! IN_RANGE {
+    ($x:expr, $from:expr, $to:expr) => {{
+        let (x, from, to) = ($x, $from, $to);
+        if from <= x && x <= to {
+            Ok(())
+        } else {
+            Err($crate::problemspec::spec::ConstraintsError::new(format!(
+                "{} <= {} <= {}, found {:?}",
+                stringify!($from),
+                stringify!($x),
+                stringify!($to),
+                x
+            )))
+        }
+    }};
+}
+
+#[macro_export]
+macro_rules! EQ {
+    ($x:expr, $y:expr) => {{
+        let (x, y) = ($x, $y);
+        if x == y {
+            Ok(())
+        } else {
+            Err($crate::problemspec::spec::ConstraintsError::new(format!(
+                "{} == {}, found {:?} and {:?}",
+                stringify!($x),
+                stringify!($y),
+                x,
+                y
+            )))
+        }
+    }};
+}
+
+#[macro_export]
+macro_rules! LEN_EQ {
+    ($v:expr, $len:expr) => {{
+        let (len, expected) = ($v.len(), $len as usize);
+        if len == expected {
+            Ok(())
+        } else {
+            Err($crate::problemspec::spec::ConstraintsError::new(format!(
+                "{}.len() == {}, found length {}, expected {}",
+                stringify!($v),
+                stringify!($len),
+                len,
+                expected
+            )))
+        }
+    }};
+}
+
+#[macro_export]
+macro_rules! ALL {
+    ($v:expr, $predicate:expr) => {{
+        let predicate = $predicate;
+        match $v.iter().enumerate().find(|(_, x)| !predicate(*x)) {
+            None => Ok(()),
+            Some((i, x)) => Err($crate::problemspec::spec::ConstraintsError::new(format!(
+                "{} for all elements of {}, found {}[{}] = {:?}",
+                stringify!($predicate),
+                stringify!($v),
+                stringify!($v),
+                i,
+                x
+            ))),
+        }
+    }};
+}
+
 #[cfg(test)]
+#[allow(clippy::useless_vec)]
 mod test {
     use super::*;
 
+    #[test]
+    fn test_checker_kind_from_str() {
+        assert_eq!("exact".parse(), Ok(CheckerKind::Exact));
+        assert_eq!("float".parse(), Ok(CheckerKind::Float(1e-6)));
+        assert_eq!("float:1e-9".parse(), Ok(CheckerKind::Float(1e-9)));
+        assert!("float:tiny".parse::<CheckerKind>().is_err());
+        assert!("float:inf".parse::<CheckerKind>().is_err());
+        assert_eq!(
+            "check.cpp".parse(),
+            Ok(CheckerKind::Custom("check.cpp".to_string()))
+        );
+    }
+
     #[test]
     fn test_line_macro() {
         assert_eq!(
@@ -273,6 +501,93 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_cons_macro() {
+        let a = 0;
+        assert!(CONS!(a == 0).is_ok());
+        assert_eq!(
+            CONS!(a > 0, a < 10, a > 1).unwrap_err().messages,
+            vec!["a > 0".to_string(), "a > 1".to_string()]
+        );
+        assert_eq!(
+            CONS!(a < 10, IN_RANGE!(a, 1, 10)).unwrap_err().messages,
+            vec!["1 <= a <= 10, found 0".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_in_range_macro() {
+        assert!(IN_RANGE!(1, 1, 10).is_ok());
+        assert!(IN_RANGE!(10, 1, 10).is_ok());
+        let a = 11;
+        assert_eq!(
+            IN_RANGE!(a, 1, 10).unwrap_err().messages,
+            vec!["1 <= a <= 10, found 11".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_eq_macro() {
+        let (n, m) = (3, 4);
+        assert!(EQ!(n, 3).is_ok());
+        assert_eq!(
+            EQ!(n, m).unwrap_err().messages,
+            vec!["n == m, found 3 and 4".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_len_eq_macro() {
+        let v = vec![1, 2, 3];
+        let n: i64 = 4;
+        assert!(LEN_EQ!(v, 3).is_ok());
+        assert_eq!(
+            LEN_EQ!(v, n).unwrap_err().messages,
+            vec!["v.len() == n, found length 3, expected 4".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_all_macro() {
+        let v = vec![1, 2, -3, -4];
+        assert!(ALL!(v, |x: &i32| *x != 0).is_ok());
+        assert_eq!(
+            ALL!(v, |x: &i32| *x > 0).unwrap_err().messages,
+            vec!["|x: &i32| *x > 0 for all elements of v, found v[2] = -3".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_constraints_error_collect() {
+        assert!(ConstraintsError::collect(vec![Ok(()), Ok(())]).is_ok());
+        assert_eq!(
+            ConstraintsError::collect(vec![
+                CONS!(1 > 2),
+                Ok(()),
+                Err(ConstraintsError {
+                    messages: vec!["a".to_string(), "b".to_string()]
+                }),
+            ])
+            .unwrap_err()
+            .messages,
+            vec!["1 > 2".to_string(), "a".to_string(), "b".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_constraints_error_with_context() {
+        let error = ConstraintsError {
+            messages: vec!["a > 0".to_string(), "b > 0".to_string()],
+        };
+        assert_eq!(
+            error.with_context("Testcase #1").messages,
+            vec![
+                "Testcase #1: a > 0".to_string(),
+                "Testcase #1: b > 0".to_string()
+            ]
+        );
+    }
+
     #[test]
     fn test_raw_lines_macro() {
         assert_eq!(
@@ -281,3 +596,127 @@ mod test {
         )
     }
 }
+
+#[cfg(all(test, feature = "derive"))]
+mod derive_test {
+    use super::*;
+    use crate::problemspec::generator::Generator;
+
+    #[derive(ProblemSpec)]
+    #[constraint(n as usize == a.len())]
+    struct Spec {
+        #[line(0)]
+        #[constraint(n > 0)]
+        n: i64,
+        #[line(0)]
+        m: u32,
+        #[bounded(n)]
+        a: Vec<i64>,
+        #[bounded(n)]
+        b: Vec<char>,
+        #[line(1)]
+        c: Vec<u32>,
+        #[grid(2, m)]
+        g: Vec<Vec<char>>,
+        #[output]
+        sum: Option<i64>,
+        #[output]
+        #[line(1)]
+        ans: Option<Vec<i64>>,
+        #[allow(dead_code)]
+        ignored: String,
+    }
+
+    #[derive(ProblemSpec)]
+    #[problem(slug = "sum", time_limit = 2000, checker = "float:1e-9")]
+    struct MetadataSpec {
+        #[line(0)]
+        a: i64,
+    }
+
+    #[derive(ProblemSpec)]
+    #[multiple_testcases(t <= 2)]
+    #[file_constraint(specs.iter().map(|spec| spec.a).sum::<i64>() <= 10)]
+    #[output_prefix("Case #{}: ")]
+    struct MultiSpec {
+        #[line(0)]
+        a: i64,
+    }
+
+    fn spec(n: i64, a: Vec<i64>) -> Spec {
+        Spec {
+            n,
+            m: 3,
+            a,
+            b: vec!['x', 'y'],
+            c: vec![7, 8, 9],
+            g: vec![vec!['a', 'b', 'c'], vec!['d', 'e', 'f']],
+            sum: Some(10),
+            ans: Some(vec![1, 2]),
+            ignored: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_derive_input_format() {
+        assert_eq!(
+            spec(2, vec![4, 5]).input_format().generate(),
+            Some("2 3\n4 x\n5 y\n7 8 9\nabc\ndef".to_string())
+        );
+    }
+
+    #[test]
+    fn test_derive_output_format() {
+        assert_eq!(
+            spec(2, vec![4, 5]).output_format().generate(),
+            Some("10\n1 2".to_string())
+        );
+    }
+
+    #[test]
+    fn test_derive_constraints() {
+        assert!(spec(2, vec![4, 5]).constraints().is_ok());
+        assert_eq!(
+            spec(0, vec![]).constraints().unwrap_err().messages,
+            vec!["n > 0".to_string()]
+        );
+        assert_eq!(
+            spec(1, vec![4, 5]).constraints().unwrap_err().messages,
+            vec!["n as usize == a.len()".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_derive_multiple_test_case_config() {
+        let config = MultiSpec::multiple_test_case_config().unwrap();
+        assert!((config.constraints)(2).is_ok());
+        assert!((config.constraints)(3).is_err());
+        assert_eq!(config.output_prefix, Some("Case #{}: ".to_string()));
+        assert_eq!(
+            MultiSpec { a: 1 }.input_format().generate(),
+            Some("1".to_string())
+        );
+        assert!(MultiSpec { a: 1 }.constraints().is_ok());
+    }
+
+    #[test]
+    fn test_derive_file_constraints() {
+        assert!(MultiSpec::file_constraints(&[MultiSpec { a: 4 }, MultiSpec { a: 6 }]).is_ok());
+        assert!(MultiSpec::file_constraints(&[MultiSpec { a: 4 }, MultiSpec { a: 7 }]).is_err());
+        assert!(Spec::file_constraints(&[]).is_ok());
+    }
+
+    #[test]
+    fn test_derive_metadata() {
+        assert!(MetadataSpec { a: 1 }.constraints().is_ok());
+        assert_eq!(
+            MetadataSpec::metadata(),
+            ProblemMetadata {
+                slug: Some("sum".to_string()),
+                time_limit: Some(2000),
+                checker: Some(CheckerKind::Float(1e-9)),
+                ..ProblemMetadata::default()
+            }
+        );
+    }
+}
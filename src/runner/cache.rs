@@ -0,0 +1,136 @@
+use std::{
+    collections::HashSet,
+    fs::{create_dir_all, read, read_dir, read_to_string, remove_file},
+    io::Error,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use super::{
+    executor::Execution,
+    export::sha256,
+    io::{sibling, write_file},
+};
+
+/// Solution outputs from previous runs, in `.<output>.testgen-cache` next to the output folder.
+/// Entries are keyed by the hashes of the input and of the solution, so changing either one runs
+/// the solution again.
+pub struct Cache {
+    folder: PathBuf,
+    solution: String,
+    /// Whether to reuse entries, or only record them.
+    read: bool,
+}
+
+impl Cache {
+    pub fn new(target: &Path, solution_command: &str, read: bool) -> Result<Self, Error> {
+        let folder = sibling(target, "cache")?;
+        create_dir_all(&folder)?;
+        Ok(Cache {
+            folder,
+            solution: solution_hash(solution_command),
+            read,
+        })
+    }
+
+    fn key(&self, input_sha256: &str) -> String {
+        sha256(format!("{}\n{}", self.solution, input_sha256).as_bytes())
+    }
+
+    pub fn get(&self, input_sha256: &str) -> Option<Execution> {
+        if !self.read {
+            return None;
+        }
+        let key = self.key(input_sha256);
+        let output = read_to_string(self.folder.join(format!("{}.out", key))).ok()?;
+        let runtime = read_to_string(self.folder.join(format!("{}.ms", key))).ok()?;
+        Some(Execution {
+            output,
+            runtime: Duration::from_millis(runtime.trim().parse().ok()?),
+        })
+    }
+
+    pub fn put(&self, input_sha256: &str, execution: &Execution) -> Result<(), Error> {
+        let key = self.key(input_sha256);
+        write_file(&execution.output, &self.folder.join(format!("{}.out", key)))?;
+        write_file(
+            &execution.runtime.as_millis().to_string(),
+            &self.folder.join(format!("{}.ms", key)),
+        )
+    }
+
+    /// Removes the entries of every input but those hashed in `inputs`, so the cache does not
+    /// outgrow the tests. Only call it after a successful run: a failed one may not have reached
+    /// every test, and its entries are still needed next time.
+    pub fn retain<'a>(&self, inputs: impl Iterator<Item = &'a str>) -> Result<(), Error> {
+        let keep: HashSet<String> = inputs.map(|input| self.key(input)).collect();
+        for entry in read_dir(&self.folder)? {
+            let path = entry?.path();
+            let key = path.file_stem().map(|stem| stem.to_string_lossy());
+            if !key.is_some_and(|key| keep.contains(key.as_ref())) {
+                remove_file(&path)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Hash of the solution command and of every file it names, e.g. the binary in `./solution` or
+/// the script in `python3 solution.py`.
+fn solution_hash(solution_command: &str) -> String {
+    let mut content = solution_command.to_string();
+    for arg in shlex::split(solution_command).unwrap_or_default() {
+        if let Ok(bytes) = read(&arg) {
+            content.push('\n');
+            content.push_str(&sha256(&bytes));
+        }
+    }
+    sha256(content.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::remove_dir_all;
+
+    use super::*;
+
+    #[test]
+    fn test_cache() {
+        let folder = std::env::temp_dir().join(format!("testgen-cache-{}", std::process::id()));
+        let _ = remove_dir_all(&folder);
+        create_dir_all(&folder).unwrap();
+        let solution = folder.join("solution.py");
+        write_file("print(1)", &solution).unwrap();
+        let command = format!("python3 {}", solution.display());
+        let target = folder.join("tc");
+        let execution = Execution {
+            output: "1\n".to_string(),
+            runtime: Duration::from_millis(12),
+        };
+
+        let (first, second) = (sha256(b"1 2\n"), sha256(b"3 4\n"));
+
+        let cache = Cache::new(&target, &command, true).unwrap();
+        assert!(cache.get(&first).is_none());
+        cache.put(&first, &execution).unwrap();
+        cache.put(&second, &execution).unwrap();
+        let cached = cache.get(&first).unwrap();
+        assert_eq!(
+            (cached.output.as_str(), cached.runtime),
+            ("1\n", Duration::from_millis(12))
+        );
+        assert!(Cache::new(&target, &command, false)
+            .unwrap()
+            .get(&first)
+            .is_none());
+
+        cache.retain(std::iter::once(second.as_str())).unwrap();
+        assert!(cache.get(&first).is_none());
+        assert!(cache.get(&second).is_some());
+
+        write_file("print(2)", &solution).unwrap();
+        let cache = Cache::new(&target, &command, true).unwrap();
+        assert!(cache.get(&second).is_none());
+        remove_dir_all(folder).unwrap();
+    }
+}
@@ -1,32 +1,172 @@
-use std::io::Write;
+use std::fs::read_to_string;
+use std::io::{Error, ErrorKind, Write};
+use std::path::PathBuf;
 use std::process::{Command, Stdio};
+use std::sync::{
+    atomic::{AtomicBool, AtomicUsize, Ordering},
+    Mutex,
+};
+use std::time::{Duration, Instant};
 
-pub fn execute(solution_command: &str, input: &str) -> String {
-    let args = shlex::split(solution_command).unwrap();
+use super::io::write_file;
 
-    let mut cmd = Command::new(&args[0]);
-    let mut child = cmd
+pub struct Execution {
+    pub output: String,
+    /// Wall-clock time from spawning the solution until it exits. With more than one job the
+    /// solutions share the machine, so this can be longer than a judge would measure; run with
+    /// one job for timings to trust.
+    pub runtime: Duration,
+}
+
+pub fn execute(solution_command: &str, input: &str) -> Result<Execution, Error> {
+    let args = shlex::split(solution_command)
+        .filter(|args| !args.is_empty())
+        .ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                format!("invalid solution command: {:?}", solution_command),
+            )
+        })?;
+
+    let start = Instant::now();
+    let mut child = Command::new(&args[0])
         .stdin(Stdio::piped())
         .stdout(Stdio::piped())
-        .args(args.iter().skip(1))
+        .args(&args[1..])
         .spawn()
-        .expect("Failed to execute solution");
+        .map_err(|err| {
+            Error::new(
+                err.kind(),
+                format!("failed to run solution {:?}: {}", solution_command, err),
+            )
+        })?;
 
-    let mut stdin = child.stdin.take().expect("failed to get stdin");
+    let mut stdin = child.stdin.take().expect("stdin is piped");
     let input = input.to_string();
-
-    std::thread::spawn(move || {
-        stdin
-            .write_all(input.as_bytes())
-            .expect("failed to write to stdin");
+    let writer = std::thread::spawn(move || match stdin.write_all(input.as_bytes()) {
+        // The solution may exit without reading all of its input.
+        Err(err) if err.kind() == ErrorKind::BrokenPipe => Ok(()),
+        result => result,
     });
 
-    let output = child.wait_with_output().unwrap();
-    let stdout = output.stdout;
-    let stdout = String::from_utf8(stdout).unwrap();
-    // let stderr = output.stderr;
+    let output = child.wait_with_output()?;
+    let runtime = start.elapsed();
+    writer.join().expect("stdin writer panicked")?;
+    let stdout = String::from_utf8(output.stdout).map_err(|err| {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("solution output is not UTF-8: {}", err),
+        )
+    })?;
     // TODO: handle stderr
-    // let stderr = String::from_utf8(stderr).unwrap();
 
-    stdout
+    Ok(Execution {
+        output: stdout,
+        runtime,
+    })
+}
+
+/// Runs the solution on every `(input, output)` pair of files with up to `jobs` processes at a
+/// time, writing each output as soon as it is known. The runtimes are returned in the order of
+/// the files, or the first error in that order. No new run starts once one has failed.
+pub fn execute_all(
+    solution_command: &str,
+    files: &[(PathBuf, PathBuf)],
+    jobs: usize,
+) -> Result<Vec<Duration>, Error> {
+    let run = |(input, output): &(PathBuf, PathBuf)| {
+        let execution = execute(solution_command, &read_to_string(input)?)?;
+        write_file(&execution.output, output)?;
+        Ok(execution.runtime)
+    };
+    let next = AtomicUsize::new(0);
+    let failed = AtomicBool::new(false);
+    let runtimes: Vec<Mutex<Option<Result<Duration, Error>>>> =
+        files.iter().map(|_| Mutex::new(None)).collect();
+    std::thread::scope(|scope| {
+        for _ in 0..jobs.clamp(1, files.len().max(1)) {
+            scope.spawn(|| loop {
+                if failed.load(Ordering::Relaxed) {
+                    break;
+                }
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                if i >= files.len() {
+                    break;
+                }
+                let runtime = run(&files[i]);
+                if runtime.is_err() {
+                    failed.store(true, Ordering::Relaxed);
+                }
+                *runtimes[i].lock().unwrap() = Some(runtime);
+            });
+        }
+    });
+    // Files are started in order, so those skipped after a failure all come after it.
+    runtimes
+        .into_iter()
+        .map_while(|runtime| runtime.into_inner().unwrap())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_execute_all_keeps_input_order() {
+        let folder = std::env::temp_dir().join(format!("testgen-executor-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&folder);
+        std::fs::create_dir_all(&folder).unwrap();
+        let files: Vec<(PathBuf, PathBuf)> = (0..20)
+            .map(|i| {
+                let input = folder.join(format!("{}.in", i));
+                write_file(&format!("{}\n", i), &input).unwrap();
+                (input, folder.join(format!("{}.out", i)))
+            })
+            .collect();
+        assert_eq!(execute_all("cat", &files, 4).unwrap().len(), 20);
+        for (i, (_, output)) in files.iter().enumerate() {
+            assert_eq!(read_to_string(output).unwrap(), format!("{}\n", i));
+        }
+        std::fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[test]
+    fn test_execute_all_stops_after_a_failure() {
+        let folder = std::env::temp_dir().join(format!("testgen-failure-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&folder);
+        std::fs::create_dir_all(&folder).unwrap();
+        let files: Vec<(PathBuf, PathBuf)> = (0..20)
+            .map(|i| {
+                let input = folder.join(format!("{}.in", i));
+                write_file(&format!("{}\n", i), &input).unwrap();
+                (input, folder.join(format!("{}.out", i)))
+            })
+            .collect();
+        // Prints invalid UTF-8 for the input 0, and echoes every other input.
+        let solution = r#"sh -c 'read x; if [ "$x" = 0 ]; then printf "\377"; else echo "$x"; fi'"#;
+        let error = execute_all(solution, &files, 1).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        assert!(files.iter().all(|(_, output)| !output.exists()));
+        std::fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[test]
+    fn test_execute_errors() {
+        assert_eq!(
+            execute("", "").err().map(|err| err.kind()),
+            Some(ErrorKind::InvalidInput)
+        );
+        assert_eq!(
+            execute("testgen-missing-solution", "")
+                .err()
+                .map(|err| err.kind()),
+            Some(ErrorKind::NotFound)
+        );
+        assert_eq!(
+            execute("printf '\\377'", "").err().map(|err| err.kind()),
+            Some(ErrorKind::InvalidData)
+        );
+        assert_eq!(execute("true", "1 2\n").unwrap().output, "");
+    }
 }
@@ -0,0 +1,176 @@
+use std::{
+    fs::{copy, create_dir_all},
+    path::Path,
+};
+
+use super::{move_test, Package, Written};
+use crate::problemspec::spec::CheckerKind;
+use crate::runner::io::write_file;
+
+/// Where the `italy_yaml` loader looks for a checker, which then compares the outputs instead of
+/// the default token comparison. `task.yaml` has no key for it. CMS runs it as is, so it must be
+/// an executable rather than a source file.
+const CHECKER: &str = "check/checker";
+
+/// CMS task in the `italy_yaml` format. Tests are numbered from 0, samples first, and `gen/GEN`
+/// lists one line per test with a `# ST: <score>` header opening every subtask; the samples form
+/// a subtask worth 0 points.
+pub fn write(base_folder: &Path, package: &Package) -> Result<Vec<Written>, std::io::Error> {
+    let (input, output) = (Path::new("input"), Path::new("output"));
+    let gen = base_folder.join("gen");
+    for folder in &[&base_folder.join(input), &base_folder.join(output), &gen] {
+        create_dir_all(folder)?;
+    }
+    let written = package
+        .tests
+        .iter()
+        .enumerate()
+        .map(|(i, test)| {
+            move_test(
+                base_folder,
+                test,
+                input.join(format!("input{}.txt", i)),
+                output.join(format!("output{}.txt", i)),
+            )
+        })
+        .collect::<Result<_, _>>()?;
+
+    // CMS compares tokens by default and has no built-in float comparison.
+    if let Some(CheckerKind::Custom(checker)) = &package.checker {
+        let path = base_folder.join(CHECKER);
+        create_dir_all(path.parent().unwrap())?;
+        copy(checker, path)?;
+    }
+
+    write_file(&gen_file(package), &gen.join("GEN"))?;
+    write_file(&task_yaml(package), &base_folder.join("task.yaml"))?;
+    Ok(written)
+}
+
+fn gen_file(package: &Package) -> String {
+    let mut gen = String::new();
+    let mut current = None;
+    for test in &package.tests {
+        let group = if test.sample { None } else { test.subtask };
+        if !package.subtasks.is_empty() && (gen.is_empty() || group != current) {
+            let score = group.map_or(0, |subtask| {
+                package
+                    .subtasks
+                    .iter()
+                    .find(|s| s.index == subtask)
+                    .map_or(0, |s| s.score)
+            });
+            gen.push_str(&format!("# ST: {}\n", score));
+            current = group;
+        }
+        match (test.sample, test.subtask) {
+            (true, _) => gen.push_str(&format!("sample {}\n", test.index + 1)),
+            (false, Some(subtask)) => {
+                gen.push_str(&format!("subtask {} {}\n", subtask + 1, test.index + 1))
+            }
+            (false, None) => gen.push_str(&format!("test {}\n", test.index + 1)),
+        }
+    }
+    gen
+}
+
+fn task_yaml(package: &Package) -> String {
+    let public: Vec<String> = package
+        .tests
+        .iter()
+        .enumerate()
+        .filter(|(_, test)| test.sample)
+        .map(|(i, _)| i.to_string())
+        .collect();
+    format!(
+        "name: '{}'\ntitle: '{}'\ntime_limit: {}\nmemory_limit: {}\nn_input: {}\npublic_testcases: '{}'\ninfile: ''\noutfile: ''\ntoken_mode: disabled\n",
+        package.slug.replace('\'', "''"),
+        package.title.replace('\'', "''"),
+        package.time_limit as f64 / 1000.0,
+        package.memory_limit,
+        package.tests.len(),
+        public.join(",")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runner::{
+        export::Subtask,
+        test_util::{self, test_file},
+    };
+
+    fn package(subtasks: Vec<Subtask>) -> Package {
+        let tests = vec![
+            test_file(None, 0, true),
+            test_file(Some(0), 0, false),
+            test_file(Some(0), 1, false),
+            test_file(Some(2), 0, false),
+        ];
+        Package {
+            time_limit: 1500,
+            ..test_util::package(tests, subtasks)
+        }
+    }
+
+    #[test]
+    fn test_gen_file_with_subtasks() {
+        let package = package(vec![
+            Subtask {
+                index: 0,
+                score: 40,
+            },
+            Subtask {
+                index: 2,
+                score: 60,
+            },
+        ]);
+        assert_eq!(
+            gen_file(&package),
+            "# ST: 0\nsample 1\n# ST: 40\nsubtask 1 1\nsubtask 1 2\n# ST: 60\nsubtask 3 1\n"
+        );
+    }
+
+    #[test]
+    fn test_gen_file_without_subtasks() {
+        let mut package = package(Vec::new());
+        package.tests.truncate(1);
+        package.tests.push(test_file(None, 0, false));
+        assert_eq!(gen_file(&package), "sample 1\ntest 1\n");
+    }
+
+    #[test]
+    fn test_custom_checker_is_found_by_the_loader() {
+        let folder = std::env::temp_dir().join(format!("testgen-cms-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&folder);
+        create_dir_all(&folder).unwrap();
+        let source = folder.join("my_checker");
+        write_file("#!/bin/sh\n", &source).unwrap();
+        let base_folder = folder.join("task");
+        create_dir_all(&base_folder).unwrap();
+
+        let mut package = package(Vec::new());
+        package.tests.clear();
+        package.checker = Some(CheckerKind::Custom(source.to_string_lossy().into_owned()));
+        write(&base_folder, &package).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(base_folder.join("check/checker")).unwrap(),
+            "#!/bin/sh\n"
+        );
+        assert!(!base_folder.join("check/my_checker").exists());
+        std::fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[test]
+    fn test_task_yaml() {
+        let mut package = package(Vec::new());
+        package.title = "It's a sum".to_string();
+        let yaml = task_yaml(&package);
+        assert!(yaml.contains("name: 'sum'\ntitle: 'It''s a sum'\n"));
+        assert!(yaml.contains("time_limit: 1.5\n"));
+        assert!(yaml.contains("memory_limit: 256\n"));
+        assert!(yaml.contains("n_input: 4\n"));
+        assert!(yaml.contains("public_testcases: '0'\n"));
+    }
+}
@@ -0,0 +1,30 @@
+use std::path::Path;
+
+use super::{move_test, Package, TestFile, Written};
+
+fn file_name(package: &Package, test: &TestFile) -> String {
+    match (test.sample, test.subtask) {
+        (true, _) => format!("sample_{}", test.index + 1),
+        (false, Some(subtask)) if package.multiple_testcases => subtask.to_string(),
+        (false, Some(subtask)) => format!("{}_{}", subtask, test.index + 1),
+        (false, None) => format!("{}", test.index + 1),
+    }
+}
+
+pub fn write(base_folder: &Path, package: &Package) -> Result<Vec<Written>, std::io::Error> {
+    let (input_extension, output_extension) =
+        (package.input_extension(), package.output_extension());
+    package
+        .tests
+        .iter()
+        .zip(package.file_names(file_name)?)
+        .map(|(test, file_name)| {
+            move_test(
+                base_folder,
+                test,
+                format!("{}.{}", file_name, input_extension).into(),
+                format!("{}.{}", file_name, output_extension).into(),
+            )
+        })
+        .collect()
+}
@@ -0,0 +1,153 @@
+use std::{
+    fs::create_dir_all,
+    path::{Path, PathBuf},
+};
+
+use super::{copy_checker, move_test, Package, TestFile, Written};
+use crate::{problemspec::spec::CheckerKind, runner::io::write_file};
+
+/// Kattis problem package, as imported by DOMjudge. Samples go to `data/sample` and official
+/// tests to `data/secret`, with one `subtaskN` group per subtask scored through
+/// `testdata.yaml`.
+pub fn write(base_folder: &Path, package: &Package) -> Result<Vec<Written>, std::io::Error> {
+    let mut written = Vec::new();
+    for test in &package.tests {
+        let folder = folder(Path::new("data"), package, test);
+        create_dir_all(base_folder.join(&folder))?;
+        written.push(move_test(
+            base_folder,
+            test,
+            folder.join(format!("{}.in", test.index + 1)),
+            folder.join(format!("{}.ans", test.index + 1)),
+        )?);
+    }
+    let data = base_folder.join("data");
+    create_dir_all(data.join("sample"))?;
+    create_dir_all(data.join("secret"))?;
+    for subtask in &package.subtasks {
+        let folder = data
+            .join("secret")
+            .join(format!("subtask{}", subtask.index + 1));
+        create_dir_all(&folder)?;
+        write_file(
+            &format!(
+                "on_reject: break\naccept_score: {}\nrange: 0 {}\ngrader_flags: min\n",
+                subtask.score, subtask.score
+            ),
+            &folder.join("testdata.yaml"),
+        )?;
+    }
+
+    if let Some(CheckerKind::Custom(checker)) = &package.checker {
+        copy_checker(
+            checker,
+            &base_folder.join("output_validators").join("checker"),
+        )?;
+    }
+
+    write_file(&problem_yaml(package), &base_folder.join("problem.yaml"))?;
+    write_file(
+        &format!(
+            "name = '{}'\ntimelimit = '{}'\n",
+            package.title.replace('\'', ""),
+            package.time_limit as f64 / 1000.0
+        ),
+        &base_folder.join("domjudge-problem.ini"),
+    )?;
+    Ok(written)
+}
+
+fn folder(data: &Path, package: &Package, test: &TestFile) -> PathBuf {
+    match (test.sample, test.subtask) {
+        (true, _) => data.join("sample"),
+        (false, Some(subtask)) if !package.subtasks.is_empty() => {
+            data.join("secret").join(format!("subtask{}", subtask + 1))
+        }
+        (false, _) => data.join("secret"),
+    }
+}
+
+fn problem_yaml(package: &Package) -> String {
+    let mut yaml = format!("name: '{}'\n", package.title.replace('\'', "''"));
+    if !package.subtasks.is_empty() {
+        yaml.push_str("type: scoring\n");
+    }
+    // The default validator compares tokens, ignoring case and whitespace.
+    match &package.checker {
+        None | Some(CheckerKind::Tokens) => {}
+        Some(CheckerKind::Exact) => {
+            yaml.push_str("validator_flags: case_sensitive space_change_sensitive\n")
+        }
+        Some(CheckerKind::Float(error)) => {
+            yaml.push_str(&format!("validator_flags: float_tolerance {}\n", error))
+        }
+        Some(CheckerKind::Custom(_)) => yaml.push_str("validation: custom\n"),
+    }
+    yaml.push_str(&format!("limits:\n    memory: {}\n", package.memory_limit));
+    yaml
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runner::{
+        export::Subtask,
+        test_util::{self, test_file},
+    };
+
+    fn package(subtasks: Vec<Subtask>, checker: Option<CheckerKind>) -> Package {
+        Package {
+            title: "it's".to_string(),
+            memory_limit: 512,
+            checker,
+            ..test_util::package(Vec::new(), subtasks)
+        }
+    }
+
+    #[test]
+    fn test_problem_yaml() {
+        assert_eq!(
+            problem_yaml(&package(Vec::new(), None)),
+            "name: 'it''s'\nlimits:\n    memory: 512\n"
+        );
+        assert_eq!(
+            problem_yaml(&package(
+                vec![Subtask {
+                    index: 0,
+                    score: 100
+                }],
+                Some(CheckerKind::Custom("check.cpp".into()))
+            )),
+            "name: 'it''s'\ntype: scoring\nvalidation: custom\nlimits:\n    memory: 512\n"
+        );
+        assert!(
+            problem_yaml(&package(Vec::new(), Some(CheckerKind::Float(1e-9))))
+                .contains("validator_flags: float_tolerance 0.000000001\n")
+        );
+    }
+
+    #[test]
+    fn test_folder() {
+        let data = Path::new("data");
+        let test = |subtask, sample| test_file(subtask, 0, sample);
+        let with_subtasks = package(
+            vec![Subtask {
+                index: 1,
+                score: 100,
+            }],
+            None,
+        );
+        assert_eq!(
+            folder(data, &with_subtasks, &test(None, true)),
+            Path::new("data/sample")
+        );
+        assert_eq!(
+            folder(data, &with_subtasks, &test(Some(1), false)),
+            Path::new("data/secret/subtask2")
+        );
+        assert_eq!(
+            folder(data, &package(Vec::new(), None), &test(None, false)),
+            Path::new("data/secret")
+        );
+    }
+}
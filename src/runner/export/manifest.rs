@@ -0,0 +1,223 @@
+use std::path::Path;
+
+use sha2::{Digest, Sha256};
+
+use super::{Format, Package, Written};
+
+pub const FILE_NAME: &str = "manifest.json";
+
+/// First key of every manifest, telling testgen output apart from other folders that happen to
+/// have a `manifest.json`.
+const GENERATOR: &str = "\"generator\": \"testgen\"";
+
+/// Whether `content` is a manifest written by testgen.
+pub fn is_testgen(content: &str) -> bool {
+    content
+        .lines()
+        .nth(1)
+        .is_some_and(|line| line.trim().trim_end_matches(',') == GENERATOR)
+}
+
+/// `manifest.json`: the problem's metadata and, for every test file, where it was written, the
+/// subtasks it belongs to, the seed it came from, the size and SHA-256 of its input and output,
+/// and how long the solution took in milliseconds.
+pub fn manifest(format: Format, package: &Package, written: &[Written]) -> String {
+    let subtasks: Vec<String> = package
+        .subtasks
+        .iter()
+        .map(|subtask| {
+            format!(
+                "{{\"subtask\": {}, \"score\": {}}}",
+                subtask.index + 1,
+                subtask.score
+            )
+        })
+        .collect();
+
+    let tests: Vec<String> = package
+        .tests
+        .iter()
+        .zip(written)
+        .map(|(test, written)| {
+            let subtasks = match (test.sample, test.subtask) {
+                (false, Some(subtask)) => format!("[{}]", subtask + 1),
+                _ => "[]".to_string(),
+            };
+            let (output, output_size, output_sha256) = match (&test.output, &written.output) {
+                (Some(stored), Some(path)) => (
+                    path_string(path),
+                    stored.size.to_string(),
+                    string(&stored.sha256),
+                ),
+                _ => ("null".to_string(), "null".to_string(), "null".to_string()),
+            };
+            format!(
+                r#"    {{
+      "input": {input},
+      "output": {output},
+      "sample": {sample},
+      "subtasks": {subtasks},
+      "seed": {seed},
+      "input_size": {input_size},
+      "input_sha256": {input_sha256},
+      "output_size": {output_size},
+      "output_sha256": {output_sha256},
+      "runtime_ms": {runtime}
+    }}"#,
+                input = path_string(&written.input),
+                output = output,
+                sample = test.sample,
+                subtasks = subtasks,
+                seed = optional(test.seed),
+                input_size = test.input.size,
+                input_sha256 = string(&test.input.sha256),
+                output_size = output_size,
+                output_sha256 = output_sha256,
+                runtime = optional(test.runtime.map(|runtime| runtime.as_millis())),
+            )
+        })
+        .collect();
+
+    format!(
+        r#"{{
+  {generator},
+  "slug": {slug},
+  "title": {title},
+  "format": "{format}",
+  "seed": {seed},
+  "time_limit_ms": {time_limit},
+  "memory_limit_mb": {memory_limit},
+  "total_score": {total_score},
+  "subtasks": [{subtasks}],
+  "tests": [
+{tests}
+  ]
+}}
+"#,
+        generator = GENERATOR,
+        slug = string(&package.slug),
+        title = string(&package.title),
+        format = format,
+        seed = package.seed,
+        time_limit = package.time_limit,
+        memory_limit = package.memory_limit,
+        total_score = optional(package.total_score),
+        subtasks = subtasks.join(", "),
+        tests = tests.join(",\n"),
+    )
+}
+
+pub fn sha256(content: &[u8]) -> String {
+    Sha256::digest(content)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+fn optional<T: ToString>(value: Option<T>) -> String {
+    value.map_or("null".to_string(), |value| value.to_string())
+}
+
+/// Paths use `/` on every platform.
+fn path_string(path: &Path) -> String {
+    string(&path.to_string_lossy().replace('\\', "/"))
+}
+
+/// A JSON string literal.
+fn string(s: &str) -> String {
+    let mut json = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if (c as u32) < 0x20 => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{path::PathBuf, time::Duration};
+
+    use super::*;
+    use crate::runner::{
+        export::{Subtask, TestFile},
+        io::Stored,
+        test_util::{package, test_file},
+    };
+
+    #[test]
+    fn test_sha256() {
+        assert_eq!(
+            sha256(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn test_string() {
+        assert_eq!(string("a\"b\\c\n\u{1}"), r#""a\"b\\c\n\u0001""#);
+    }
+
+    #[test]
+    fn test_is_testgen() {
+        assert!(is_testgen(
+            "{\n  \"generator\": \"testgen\",\n  \"slug\": \"sum\"\n}\n"
+        ));
+        assert!(!is_testgen("{}"));
+        assert!(!is_testgen("{\n  \"name\": \"testgen\"\n}\n"));
+    }
+
+    #[test]
+    fn test_manifest() {
+        let tests = vec![
+            TestFile {
+                input: Stored::new(PathBuf::new(), "1 2\n"),
+                output: Some(Stored::new(PathBuf::new(), "3\n")),
+                ..test_file(None, 0, true)
+            },
+            TestFile {
+                input: Stored::new(PathBuf::new(), "abc"),
+                seed: Some(42),
+                runtime: Some(Duration::from_millis(15)),
+                ..test_file(Some(1), 0, false)
+            },
+        ];
+        let subtasks = vec![Subtask {
+            index: 1,
+            score: 60,
+        }];
+        let package = Package {
+            title: "Sum \"2\"".to_string(),
+            seed: 7,
+            total_score: Some(60),
+            ..package(tests, subtasks)
+        };
+        let written = vec![
+            Written {
+                input: PathBuf::from("sample_1.in"),
+                output: Some(PathBuf::from("sample_1.out")),
+            },
+            Written {
+                input: PathBuf::from("1_1.in"),
+                output: None,
+            },
+        ];
+        let json = manifest(Format::Flat, &package, &written);
+        assert!(is_testgen(&json));
+        assert!(json.contains("\"title\": \"Sum \\\"2\\\"\",\n"));
+        assert!(json.contains("\"subtasks\": [{\"subtask\": 2, \"score\": 60}],\n"));
+        assert!(json.contains("\"input\": \"sample_1.in\",\n      \"output\": \"sample_1.out\""));
+        assert!(json.contains("\"subtasks\": [2],\n      \"seed\": 42,"));
+        assert!(json.contains(
+            "\"input_sha256\": \"ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad\""
+        ));
+        assert!(json.contains("\"output_sha256\": null,\n      \"runtime_ms\": 15\n"));
+    }
+}
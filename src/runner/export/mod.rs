@@ -0,0 +1,284 @@
+use std::{
+    collections::HashSet,
+    fmt,
+    fs::{remove_file, rename, File},
+    io::{copy, Error, ErrorKind},
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
+
+use zip::{write::FileOptions, ZipWriter};
+
+use crate::{
+    problemspec::spec::{CheckerKind, NamingConfig},
+    runner::io::{sibling, write_file, Stored},
+};
+
+mod cms;
+mod flat;
+mod kattis;
+mod manifest;
+mod naming;
+mod polygon;
+mod tcframe;
+
+pub use self::manifest::{is_testgen, sha256, FILE_NAME as MANIFEST};
+
+/// A test file in the `Store`, with its expected output when known.
+pub struct TestFile {
+    /// Subtask the file belongs to, from 0. `None` for samples and single-task problems.
+    pub subtask: Option<usize>,
+    /// Position of the file among the samples or within its subtask, from 0.
+    pub index: usize,
+    pub sample: bool,
+    pub input: Stored,
+    pub output: Option<Stored>,
+    /// Seed of the `Random` the test was generated from, or for a file of several test cases,
+    /// the seed their streams were derived from. `None` for samples.
+    pub seed: Option<u64>,
+    /// How long the solution took to produce the output.
+    pub runtime: Option<Duration>,
+}
+
+/// Where a test file was written, relative to the output folder.
+pub struct Written {
+    pub input: PathBuf,
+    pub output: Option<PathBuf>,
+}
+
+pub struct Subtask {
+    /// From 0, like `TestFile::subtask`.
+    pub index: usize,
+    pub score: u8,
+}
+
+/// Everything an exporter needs to lay out a problem.
+pub struct Package {
+    /// Short identifier of the problem, used in file names and package metadata.
+    pub slug: String,
+    pub title: String,
+    /// Seed given on the command line.
+    pub seed: u64,
+    /// Samples first, then the official tests in subtask order.
+    pub tests: Vec<TestFile>,
+    /// Empty for single-task problems.
+    pub subtasks: Vec<Subtask>,
+    /// Whether every file holds several test cases, see `MultipleTestcaseConfig`.
+    pub multiple_testcases: bool,
+    /// In milliseconds.
+    pub time_limit: u64,
+    /// In megabytes.
+    pub memory_limit: u64,
+    /// The spec's total score, or the sum of the subtask scores.
+    pub total_score: Option<u32>,
+    /// `None` leaves the format's default checker.
+    pub checker: Option<CheckerKind>,
+    /// File names for the flat and tcframe layouts.
+    pub naming: NamingConfig,
+}
+
+impl Package {
+    /// Names of the test files without extension, from the naming template when there is one
+    /// and from `default` otherwise. Fails when two tests would share a name.
+    fn file_names(&self, default: fn(&Package, &TestFile) -> String) -> Result<Vec<String>, Error> {
+        let names = self
+            .tests
+            .iter()
+            .map(|test| match &self.naming.template {
+                Some(template) => naming::expand(template, &self.slug, test)
+                    .map_err(|message| Error::new(ErrorKind::InvalidInput, message)),
+                None => Ok(default(self, test)),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut seen = HashSet::new();
+        if let Some(name) = names.iter().find(|name| !seen.insert(*name)) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("several test files are named {:?}", name),
+            ));
+        }
+        Ok(names)
+    }
+
+    fn input_extension(&self) -> &str {
+        self.naming.input_extension.as_deref().unwrap_or("in")
+    }
+
+    fn output_extension(&self) -> &str {
+        self.naming.output_extension.as_deref().unwrap_or("out")
+    }
+}
+
+/// Moves the input of `test`, and its output when known, from the `Store` to the given paths
+/// relative to `base_folder`.
+fn move_test(
+    base_folder: &Path,
+    test: &TestFile,
+    input: PathBuf,
+    output: PathBuf,
+) -> Result<Written, Error> {
+    rename(&test.input.path, base_folder.join(&input))?;
+    let output = match &test.output {
+        Some(stored) => {
+            rename(&stored.path, base_folder.join(&output))?;
+            Some(output)
+        }
+        None => None,
+    };
+    Ok(Written { input, output })
+}
+
+/// Directory layout of the generated tests.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    /// `sample_1.in`, `0_1.in`, ... in the output folder.
+    Flat,
+    /// Codeforces Polygon package.
+    Polygon,
+    /// Kattis problem package, also used by DOMjudge.
+    Kattis,
+    /// CMS task in the `italy_yaml` format.
+    Cms,
+    /// tcframe's `<slug>_sample_1.in`, `<slug>_1_1.in`, ... with subtasks numbered from 1.
+    Tcframe,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "flat" => Ok(Format::Flat),
+            "polygon" => Ok(Format::Polygon),
+            "kattis" => Ok(Format::Kattis),
+            "cms" => Ok(Format::Cms),
+            "tcframe" => Ok(Format::Tcframe),
+            _ => Err(format!(
+                "unknown format {:?}, expected flat, polygon, kattis, cms or tcframe",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Format::Flat => "flat",
+            Format::Polygon => "polygon",
+            Format::Kattis => "kattis",
+            Format::Cms => "cms",
+            Format::Tcframe => "tcframe",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Lays out the package in `format`, then lists the written files in `manifest.json`.
+pub fn write(format: Format, base_folder: &Path, package: &Package) -> Result<(), std::io::Error> {
+    let written = match format {
+        Format::Flat => flat::write(base_folder, package),
+        Format::Polygon => polygon::write(base_folder, package),
+        Format::Kattis => kattis::write(base_folder, package),
+        Format::Cms => cms::write(base_folder, package),
+        Format::Tcframe => tcframe::write(base_folder, package),
+    }?;
+    write_file(
+        &manifest::manifest(format, package, &written),
+        &base_folder.join(manifest::FILE_NAME),
+    )
+}
+
+/// Packs the files under `base_folder` into `<name>.zip` next to it, with paths relative to it.
+/// The archive is written to a temporary file first, so a failure leaves any previous one intact.
+pub fn zip_folder(base_folder: &Path) -> Result<(), std::io::Error> {
+    let name = base_folder
+        .file_name()
+        .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "output must name a folder"))?;
+    let zip_path = base_folder.with_file_name(format!("{}.zip", name.to_string_lossy()));
+    let temp = sibling(base_folder, "zip")?;
+    if let Err(error) = write_zip(base_folder, &temp) {
+        let _ = remove_file(&temp);
+        return Err(error);
+    }
+    rename(temp, zip_path)
+}
+
+fn write_zip(base_folder: &Path, zip_path: &Path) -> Result<(), std::io::Error> {
+    let mut zip = ZipWriter::new(File::create(zip_path)?);
+    let mut folders = vec![base_folder.to_path_buf()];
+    while let Some(folder) = folders.pop() {
+        let mut entries: Vec<_> = std::fs::read_dir(&folder)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<_, _>>()?;
+        entries.sort();
+        for path in entries {
+            if path.is_dir() {
+                folders.push(path);
+                continue;
+            }
+            let name = path.strip_prefix(base_folder).unwrap().to_string_lossy();
+            zip.start_file(name.replace('\\', "/"), FileOptions::default())?;
+            copy(&mut File::open(&path)?, &mut zip)?;
+        }
+    }
+    zip.finish()?;
+    Ok(())
+}
+
+/// Slug of a problem that does not set one: the name of its output folder.
+pub fn default_slug(base_folder: &Path) -> String {
+    base_folder
+        .file_name()
+        .map_or("problem".to_string(), |name| {
+            name.to_string_lossy().into_owned()
+        })
+}
+
+/// Copies the source of a custom checker into `folder`, returning its file name.
+fn copy_checker(source: &str, folder: &Path) -> Result<String, Error> {
+    let source = Path::new(source);
+    let name = source
+        .file_name()
+        .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "checker is not a file"))?;
+    std::fs::create_dir_all(folder)?;
+    std::fs::copy(source, folder.join(name))?;
+    Ok(name.to_string_lossy().into_owned())
+}
+
+/// Escapes text for XML attributes and elements.
+fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{create_dir_all, remove_dir_all};
+
+    use zip::ZipArchive;
+
+    use super::*;
+
+    #[test]
+    fn test_zip_folder_next_to_output() {
+        let folder = std::env::temp_dir().join(format!("testgen-zip-{}", std::process::id()));
+        let _ = remove_dir_all(&folder);
+        create_dir_all(folder.join("tc/tests")).unwrap();
+        write_file("1 2\n", &folder.join("tc/tests/01")).unwrap();
+        write_file("{}", &folder.join("tc/manifest.json")).unwrap();
+
+        zip_folder(&folder.join("tc/")).unwrap();
+        assert!(!folder.join("tc/.zip").exists());
+        assert!(!folder.join(".tc.testgen-zip").exists());
+        let mut zip = ZipArchive::new(File::open(folder.join("tc.zip")).unwrap()).unwrap();
+        let mut names: Vec<&str> = zip.file_names().collect();
+        names.sort_unstable();
+        assert_eq!(names, ["manifest.json", "tests/01"]);
+        assert_eq!(zip.by_name("tests/01").unwrap().size(), 4);
+        remove_dir_all(folder).unwrap();
+    }
+}
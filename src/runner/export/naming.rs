@@ -0,0 +1,74 @@
+use super::TestFile;
+
+/// Expands a naming template, see `NamingConfig::template`, for one test file.
+pub fn expand(template: &str, slug: &str, test: &TestFile) -> Result<String, String> {
+    let mut name = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        name.push_str(&rest[..start]);
+        let end = rest[start..]
+            .find('}')
+            .ok_or_else(|| format!("unclosed placeholder in {:?}", template))?;
+        let placeholder = &rest[start + 1..start + end];
+        let (key, width) = match placeholder.split_once(':') {
+            Some((key, width)) => match width.strip_prefix('0').map(str::parse::<usize>) {
+                Some(Ok(width)) => (key, width),
+                _ => {
+                    return Err(format!(
+                        "invalid padding in {{{}}}, expected e.g. {{{}:02}}",
+                        placeholder, key
+                    ))
+                }
+            },
+            None => (placeholder, 0),
+        };
+        let number = |n: usize| format!("{:0width$}", n, width = width);
+        match key {
+            "slug" => name.push_str(slug),
+            "subtask" if test.sample => name.push_str("sample"),
+            "subtask" => name.push_str(&number(test.subtask.map_or(1, |subtask| subtask + 1))),
+            "case" => name.push_str(&number(test.index + 1)),
+            _ => {
+                return Err(format!(
+                    "unknown placeholder {{{}}}, expected slug, subtask or case",
+                    key
+                ))
+            }
+        }
+        rest = &rest[start + end + 1..];
+    }
+    name.push_str(rest);
+    Ok(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runner::test_util::test_file as test;
+
+    #[test]
+    fn test_expand() {
+        let template = "{slug}_{subtask}_{case:02}";
+        assert_eq!(
+            expand(template, "sum", &test(Some(1), 2, false)).unwrap(),
+            "sum_2_03"
+        );
+        assert_eq!(
+            expand(template, "sum", &test(None, 0, true)).unwrap(),
+            "sum_sample_01"
+        );
+        assert_eq!(
+            expand("{subtask:03}-{case}", "sum", &test(None, 11, false)).unwrap(),
+            "001-12"
+        );
+        assert_eq!(expand("tc", "sum", &test(None, 0, false)).unwrap(), "tc");
+    }
+
+    #[test]
+    fn test_expand_rejects_bad_templates() {
+        let test = test(None, 0, false);
+        assert!(expand("{name}", "sum", &test).is_err());
+        assert!(expand("{case", "sum", &test).is_err());
+        assert!(expand("{case:2}", "sum", &test).is_err());
+    }
+}
@@ -0,0 +1,235 @@
+use std::{
+    fs::create_dir_all,
+    io::{Error, ErrorKind},
+    path::Path,
+};
+
+use super::{copy_checker, escape_xml, move_test, Package, Written};
+use crate::{problemspec::spec::CheckerKind, runner::io::write_file};
+
+/// Polygon numbers tests globally from 1, samples first, as `tests/01` with answers in
+/// `tests/01.a`. Subtask `i` becomes group `i + 1` and the samples form group 0.
+pub fn write(base_folder: &Path, package: &Package) -> Result<Vec<Written>, std::io::Error> {
+    let tests = Path::new("tests");
+    create_dir_all(base_folder.join(tests))?;
+    let written = package
+        .tests
+        .iter()
+        .enumerate()
+        .map(|(i, test)| {
+            move_test(
+                base_folder,
+                test,
+                tests.join(format!("{:02}", i + 1)),
+                tests.join(format!("{:02}.a", i + 1)),
+            )
+        })
+        .collect::<Result<_, _>>()?;
+    let checker_source = match &package.checker {
+        Some(CheckerKind::Custom(checker)) => {
+            let source_type = source_type(checker)?;
+            Some((
+                copy_checker(checker, &base_folder.join("files"))?,
+                source_type,
+            ))
+        }
+        _ => None,
+    };
+    write_file(
+        &problem_xml(
+            package,
+            checker_source
+                .as_ref()
+                .map(|(name, source_type)| (name.as_str(), *source_type)),
+        ),
+        &base_folder.join("problem.xml"),
+    )?;
+    Ok(written)
+}
+
+/// Polygon's language for a source file, from its extension.
+fn source_type(source: &str) -> Result<&'static str, Error> {
+    let extension = Path::new(source)
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase());
+    match extension.as_deref() {
+        Some("cpp") | Some("cc") | Some("cxx") => Ok("cpp.g++17"),
+        Some("c") => Ok("c.gcc"),
+        Some("java") => Ok("java11"),
+        Some("pas") | Some("dpr") => Ok("pas.fpc"),
+        Some("py") => Ok("python.3"),
+        _ => Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "cannot tell the language of checker {}, expected a .c, .cpp, .java, .pas or .py file",
+                source
+            ),
+        )),
+    }
+}
+
+/// The `<assets>` element: a testlib standard checker, or the custom one copied to `files/`,
+/// given by its file name and language.
+fn assets(package: &Package, checker_source: Option<(&str, &str)>) -> String {
+    let checker = match (&package.checker, checker_source) {
+        (Some(CheckerKind::Custom(_)), Some((source, source_type))) => format!(
+            "        <checker type=\"testlib\">\n            <source path=\"files/{}\" type=\"{}\"/>\n        </checker>\n",
+            escape_xml(source),
+            source_type
+        ),
+        (Some(kind), _) => {
+            let name = match kind {
+                CheckerKind::Exact => "fcmp",
+                CheckerKind::Float(error) if *error >= 1e-4 => "rcmp4",
+                CheckerKind::Float(error) if *error >= 1e-6 => "rcmp6",
+                CheckerKind::Float(_) => "rcmp9",
+                _ => "wcmp",
+            };
+            format!(
+                "        <checker name=\"std::{}.cpp\" type=\"testlib\"/>\n",
+                name
+            )
+        }
+        (None, _) => return String::new(),
+    };
+    format!("    <assets>\n{}    </assets>\n", checker)
+}
+
+fn problem_xml(package: &Package, checker_source: Option<(&str, &str)>) -> String {
+    let with_groups = !package.subtasks.is_empty();
+
+    let mut tests = String::new();
+    for test in &package.tests {
+        let sample = if test.sample { " sample=\"true\"" } else { "" };
+        let group = match (with_groups, test.subtask) {
+            (false, _) => String::new(),
+            (true, Some(subtask)) => format!(" group=\"{}\"", subtask + 1),
+            (true, None) => " group=\"0\"".to_string(),
+        };
+        tests.push_str(&format!(
+            "                <test method=\"manual\"{}{}/>\n",
+            sample, group
+        ));
+    }
+
+    let groups = if with_groups {
+        let mut groups = String::from("            <groups>\n");
+        groups.push_str(
+            "                <group feedback-policy=\"complete\" name=\"0\" points=\"0\" points-policy=\"each-test\"/>\n",
+        );
+        for subtask in &package.subtasks {
+            groups.push_str(&format!(
+                "                <group feedback-policy=\"icpc\" name=\"{}\" points=\"{}\" points-policy=\"complete-group\"/>\n",
+                subtask.index + 1,
+                subtask.score
+            ));
+        }
+        groups.push_str("            </groups>\n");
+        groups
+    } else {
+        String::new()
+    };
+
+    format!(
+        r#"<?xml version="1.0" encoding="utf-8" standalone="no"?>
+<problem short-name="{short_name}">
+    <names>
+        <name language="english" value="{title}"/>
+    </names>
+    <judging input-file="" output-file="">
+        <testset name="tests">
+            <time-limit>{time_limit}</time-limit>
+            <memory-limit>{memory_limit}</memory-limit>
+            <test-count>{test_count}</test-count>
+            <input-path-pattern>tests/%02d</input-path-pattern>
+            <answer-path-pattern>tests/%02d.a</answer-path-pattern>
+            <tests>
+{tests}            </tests>
+{groups}        </testset>
+    </judging>
+{assets}</problem>
+"#,
+        short_name = escape_xml(&package.slug),
+        title = escape_xml(&package.title),
+        time_limit = package.time_limit,
+        memory_limit = package.memory_limit * 1024 * 1024,
+        test_count = package.tests.len(),
+        tests = tests,
+        groups = groups,
+        assets = assets(package, checker_source),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runner::{
+        export::Subtask,
+        test_util::{package, test_file},
+    };
+
+    #[test]
+    fn test_problem_xml_groups() {
+        let tests = vec![
+            test_file(None, 0, true),
+            test_file(Some(0), 0, false),
+            test_file(Some(1), 0, false),
+        ];
+        let subtasks = vec![
+            Subtask {
+                index: 0,
+                score: 30,
+            },
+            Subtask {
+                index: 1,
+                score: 70,
+            },
+        ];
+        let package = Package {
+            slug: "a+b".to_string(),
+            title: "A & B".to_string(),
+            time_limit: 2000,
+            ..package(tests, subtasks)
+        };
+        let xml = problem_xml(&package, None);
+        assert!(xml.contains("<problem short-name=\"a+b\">"));
+        assert!(xml.contains("<name language=\"english\" value=\"A &amp; B\"/>"));
+        assert!(!xml.contains("<assets>"));
+        assert!(xml.contains("<time-limit>2000</time-limit>"));
+        assert!(xml.contains("<memory-limit>268435456</memory-limit>"));
+        assert!(xml.contains("<test-count>3</test-count>"));
+        assert!(xml.contains("<test method=\"manual\" sample=\"true\" group=\"0\"/>"));
+        assert!(xml.contains("<test method=\"manual\" group=\"2\"/>"));
+        assert!(xml.contains("name=\"2\" points=\"70\" points-policy=\"complete-group\""));
+    }
+
+    #[test]
+    fn test_problem_xml_without_subtasks() {
+        let tests = vec![test_file(None, 0, true), test_file(None, 0, false)];
+        let package = Package {
+            checker: Some(CheckerKind::Float(1e-6)),
+            ..package(tests, Vec::new())
+        };
+        let xml = problem_xml(&package, None);
+        assert!(xml.contains("<checker name=\"std::rcmp6.cpp\" type=\"testlib\"/>"));
+        assert!(xml.contains("<test method=\"manual\" sample=\"true\"/>"));
+        assert!(xml.contains("<test method=\"manual\"/>"));
+        assert!(!xml.contains("<groups>"));
+
+        let package = Package {
+            checker: Some(CheckerKind::Custom("check/check.py".to_string())),
+            ..package
+        };
+        let xml = problem_xml(&package, Some(("check.py", "python.3")));
+        assert!(xml.contains("<source path=\"files/check.py\" type=\"python.3\"/>"));
+    }
+
+    #[test]
+    fn test_source_type() {
+        assert_eq!(source_type("checker.cpp").unwrap(), "cpp.g++17");
+        assert_eq!(source_type("check/Checker.C").unwrap(), "c.gcc");
+        assert_eq!(source_type("check.py").unwrap(), "python.3");
+        assert!(source_type("checker").is_err());
+        assert!(source_type("checker.rs").is_err());
+    }
+}
@@ -0,0 +1,72 @@
+use std::path::Path;
+
+use super::{move_test, Package, TestFile, Written};
+
+/// tcframe names files after the slug, with samples first and subtasks numbered from 1:
+/// `<slug>_sample_1.in`, then `<slug>_1_1.in` or `<slug>_1.in` without subtasks. With multiple
+/// test cases per file, each subtask has a single `<slug>_1.in`, and the samples `<slug>_sample.in`.
+fn file_name(package: &Package, test: &TestFile) -> String {
+    let slug = &package.slug;
+    match (test.sample, test.subtask) {
+        (true, _) if package.multiple_testcases => format!("{}_sample", slug),
+        (true, _) => format!("{}_sample_{}", slug, test.index + 1),
+        (false, Some(subtask)) if package.multiple_testcases => {
+            format!("{}_{}", slug, subtask + 1)
+        }
+        (false, Some(subtask)) => format!("{}_{}_{}", slug, subtask + 1, test.index + 1),
+        (false, None) => format!("{}_{}", slug, test.index + 1),
+    }
+}
+
+pub fn write(base_folder: &Path, package: &Package) -> Result<Vec<Written>, std::io::Error> {
+    let (input_extension, output_extension) =
+        (package.input_extension(), package.output_extension());
+    package
+        .tests
+        .iter()
+        .zip(package.file_names(file_name)?)
+        .map(|(test, file_name)| {
+            move_test(
+                base_folder,
+                test,
+                format!("{}.{}", file_name, input_extension).into(),
+                format!("{}.{}", file_name, output_extension).into(),
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runner::test_util::{package, test_file};
+
+    fn names(multiple_testcases: bool, tests: &[(Option<usize>, usize, bool)]) -> Vec<String> {
+        let package = Package {
+            multiple_testcases,
+            ..package(Vec::new(), Vec::new())
+        };
+        tests
+            .iter()
+            .map(|&(subtask, index, sample)| {
+                file_name(&package, &test_file(subtask, index, sample))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_file_names() {
+        assert_eq!(
+            names(
+                false,
+                &[(None, 1, true), (Some(0), 0, false), (Some(2), 4, false)]
+            ),
+            vec!["sum_sample_2", "sum_1_1", "sum_3_5"]
+        );
+        assert_eq!(names(false, &[(None, 2, false)]), vec!["sum_3"]);
+        assert_eq!(
+            names(true, &[(None, 0, true), (Some(1), 0, false)]),
+            vec!["sum_sample", "sum_2"]
+        );
+    }
+}
@@ -1,20 +1,254 @@
 use std::{
-    fs::{create_dir, remove_dir_all, File},
-    io::Write,
+    cell::Cell,
+    fs::{create_dir, read_dir, read_to_string, remove_dir_all, rename, File},
+    io::{Error, ErrorKind, Write},
     path::{Path, PathBuf},
 };
 
+use super::export::{is_testgen, sha256, MANIFEST};
+
 pub fn write_file(content: &str, path: &PathBuf) -> Result<(), std::io::Error> {
     let mut input_file = File::create(path)?;
     input_file.write_all(content.as_bytes())?;
     Ok(())
 }
 
-pub fn prepare_folder<'a>(base_folder: &'a str) -> Result<&'a Path, std::io::Error> {
-    let base_folder = Path::new(base_folder);
-    if base_folder.exists() {
-        remove_dir_all(&base_folder)?;
+/// A fresh folder next to the output folder, which replaces the output folder on `commit`. A
+/// run that fails before that leaves the previous output untouched.
+pub struct StagingFolder {
+    target: PathBuf,
+    temp: PathBuf,
+    committed: bool,
+}
+
+impl StagingFolder {
+    /// Refuses to ever replace the working directory or one of its parents, and without `force`
+    /// any non-empty folder that has no `manifest.json` written by a previous run.
+    pub fn new(target: &str, force: bool) -> Result<Self, Error> {
+        let target = PathBuf::from(target);
+        if target.exists() {
+            check_replaceable(&target, force)?;
+        }
+        let temp = sibling(&target, "tmp")?;
+        if temp.exists() {
+            remove_dir_all(&temp)?;
+        }
+        create_dir(&temp)?;
+        Ok(StagingFolder {
+            target,
+            temp,
+            committed: false,
+        })
+    }
+
+    /// Where the files are written until `commit`.
+    pub fn path(&self) -> &Path {
+        &self.temp
+    }
+
+    /// The output folder.
+    pub fn target(&self) -> &Path {
+        &self.target
+    }
+
+    /// Moves the staged files into place, replacing the previous output.
+    pub fn commit(mut self) -> Result<(), Error> {
+        let old = sibling(&self.target, "old")?;
+        if old.exists() {
+            remove_dir_all(&old)?;
+        }
+        let replacing = self.target.exists();
+        if replacing {
+            rename(&self.target, &old)?;
+        }
+        if let Err(error) = rename(&self.temp, &self.target) {
+            if replacing {
+                rename(&old, &self.target)?;
+            }
+            return Err(error);
+        }
+        self.committed = true;
+        if replacing {
+            remove_dir_all(&old)?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for StagingFolder {
+    fn drop(&mut self) {
+        if !self.committed {
+            let _ = remove_dir_all(&self.temp);
+        }
+    }
+}
+
+fn check_replaceable(target: &Path, force: bool) -> Result<(), Error> {
+    if !target.is_dir() {
+        return Err(Error::new(
+            ErrorKind::AlreadyExists,
+            format!("{} exists and is not a folder", target.display()),
+        ));
+    }
+    let canonical = target.canonicalize()?;
+    if std::env::current_dir()?.starts_with(&canonical) {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "refusing to replace {}, which contains the working directory",
+                target.display()
+            ),
+        ));
+    }
+    let empty = read_dir(target)?.next().is_none();
+    let generated =
+        read_to_string(target.join(MANIFEST)).is_ok_and(|manifest| is_testgen(&manifest));
+    if !force && !empty && !generated {
+        return Err(Error::new(
+            ErrorKind::AlreadyExists,
+            format!(
+                "refusing to replace {}: it has no {} from testgen so it does not look like testgen output, pass --force to replace it anyway",
+                target.display(),
+                MANIFEST
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Test files written to `.tests` in the staging folder as soon as they are generated, so only
+/// their size and hash stay in memory until the exporter moves them into place.
+pub struct Store {
+    folder: PathBuf,
+    count: Cell<usize>,
+}
+
+/// A file in the `Store`.
+#[derive(Clone, Debug, Default)]
+pub struct Stored {
+    pub path: PathBuf,
+    pub size: usize,
+    pub sha256: String,
+}
+
+impl Stored {
+    /// Describes `content`, which was written to `path`.
+    pub fn new(path: PathBuf, content: &str) -> Self {
+        Stored {
+            path,
+            size: content.len(),
+            sha256: sha256(content.as_bytes()),
+        }
+    }
+}
+
+impl Store {
+    pub fn new(staging: &Path) -> Result<Self, Error> {
+        let folder = staging.join(".tests");
+        create_dir(&folder)?;
+        Ok(Store {
+            folder,
+            count: Cell::new(0),
+        })
+    }
+
+    /// A path no other file in the store uses, for a file written elsewhere.
+    pub fn reserve(&self) -> PathBuf {
+        let count = self.count.get();
+        self.count.set(count + 1);
+        self.folder.join(count.to_string())
+    }
+
+    pub fn write(&self, content: &str) -> Result<Stored, Error> {
+        let path = self.reserve();
+        write_file(content, &path)?;
+        Ok(Stored::new(path, content))
+    }
+
+    /// Removes the files the exporter did not move.
+    pub fn remove(self) -> Result<(), Error> {
+        remove_dir_all(&self.folder)
+    }
+}
+
+/// `.<name>.testgen-<suffix>` next to `target`, on the same file system so it can be renamed
+/// into place.
+pub fn sibling(target: &Path, suffix: &str) -> Result<PathBuf, Error> {
+    let name = target
+        .file_name()
+        .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "output must name a folder"))?;
+    Ok(target.with_file_name(format!(".{}.testgen-{}", name.to_string_lossy(), suffix)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::create_dir_all;
+
+    use super::*;
+
+    const PREVIOUS_MANIFEST: &str = "{\n  \"generator\": \"testgen\",\n  \"tests\": []\n}\n";
+
+    fn scratch(name: &str) -> PathBuf {
+        let folder =
+            std::env::temp_dir().join(format!("testgen-io-{}-{}", name, std::process::id()));
+        let _ = remove_dir_all(&folder);
+        create_dir_all(&folder).unwrap();
+        folder
+    }
+
+    #[test]
+    fn test_refuses_folders_without_manifest() {
+        let folder = scratch("refuse");
+        let target = folder.join("src");
+        create_dir_all(&target).unwrap();
+        write_file("fn main() {}", &target.join("main.rs")).unwrap();
+        let target = target.to_str().unwrap();
+
+        assert!(StagingFolder::new(target, false).is_err());
+        assert!(!folder.join(".src.testgen-tmp").exists());
+
+        let staging = StagingFolder::new(target, true).unwrap();
+        write_file("1\n", &staging.path().join("1.in")).unwrap();
+        staging.commit().unwrap();
+        assert!(folder.join("src/1.in").exists());
+        assert!(!folder.join("src/main.rs").exists());
+        remove_dir_all(folder).unwrap();
+    }
+
+    #[test]
+    fn test_refuses_other_manifests() {
+        let folder = scratch("other-manifest");
+        let target = folder.join("extension");
+        create_dir_all(&target).unwrap();
+        write_file("{\n  \"name\": \"extension\"\n}\n", &target.join(MANIFEST)).unwrap();
+        assert!(StagingFolder::new(target.to_str().unwrap(), false).is_err());
+        remove_dir_all(folder).unwrap();
+    }
+
+    #[test]
+    fn test_replaces_previous_output_on_commit_only() {
+        let folder = scratch("replace");
+        let target = folder.join("tc");
+        create_dir_all(&target).unwrap();
+        write_file(PREVIOUS_MANIFEST, &target.join(MANIFEST)).unwrap();
+        let target = target.to_str().unwrap();
+
+        let staging = StagingFolder::new(target, false).unwrap();
+        write_file("1\n", &staging.path().join("1.in")).unwrap();
+        drop(staging);
+        assert!(folder.join("tc").join(MANIFEST).exists());
+        assert!(!folder.join(".tc.testgen-tmp").exists());
+
+        let staging = StagingFolder::new(target, false).unwrap();
+        write_file("1\n", &staging.path().join("1.in")).unwrap();
+        staging.commit().unwrap();
+        assert!(folder.join("tc/1.in").exists());
+        assert!(!folder.join("tc").join(MANIFEST).exists());
+        remove_dir_all(folder).unwrap();
+    }
+
+    #[test]
+    fn test_refuses_working_directory() {
+        assert!(StagingFolder::new(".", true).is_err());
     }
-    create_dir(&base_folder)?;
-    Ok(base_folder)
 }
@@ -1,47 +1,239 @@
+use std::path::Path;
+
 use crate::{
-    problemspec::spec::{MultitaskProblemSpec, ProblemSpec},
+    problemspec::spec::{CheckerKind, MultitaskProblemSpec, NamingConfig, ProblemSpec},
     testspec::spec::{MultitaskTestSpec, SingletaskTestSpec},
 };
 
 use self::{
-    io::prepare_folder, sample::GenerateSampleTestCaseError, testcase::GenerateInputOutputError,
+    cache::Cache,
+    export::{Package, Subtask},
+    io::{StagingFolder, Store},
+    sample::GenerateSampleTestCaseError,
+    testcase::{ConstraintViolation, GenerateInputOutputError},
 };
 use thiserror::Error;
 
+pub use self::export::Format;
+
+mod cache;
 mod executor;
+mod export;
 mod io;
 mod sample;
+#[cfg(test)]
+mod test_util;
 mod testcase;
 
+pub struct GenerateConfig {
+    pub output: String,
+    pub solution: Option<String>,
+    pub seed: u64,
+    /// Check every test case and report all constraint violations at the end, instead of
+    /// stopping at the first one. Nothing is written either way when a test fails.
+    pub keep_going: bool,
+    pub format: Format,
+    /// In milliseconds. This and the options below override the spec's `metadata`.
+    pub time_limit: Option<u64>,
+    /// In megabytes.
+    pub memory_limit: Option<u64>,
+    /// For package formats.
+    pub checker: Option<CheckerKind>,
+    /// Also pack the output folder into `<output>.zip`.
+    pub zip: bool,
+    /// How many solution processes to run at once. With more than one, runtimes are measured
+    /// while the processes compete for the machine, so time limit warnings may be pessimistic.
+    pub jobs: usize,
+    /// Run the solution on every test instead of reusing the outputs cached by previous runs.
+    pub no_cache: bool,
+    /// Replace the output folder even when it does not look like testgen output.
+    pub force: bool,
+    /// Problem identifier for file names and packages, defaulting to the output folder name.
+    pub slug: Option<String>,
+    /// Overrides the spec's `naming_config` field by field.
+    pub naming: NamingConfig,
+}
+
+const DEFAULT_TIME_LIMIT: u64 = 1000;
+const DEFAULT_MEMORY_LIMIT: u64 = 256;
+
+impl GenerateConfig {
+    fn time_limit<T: ProblemSpec<T>>(&self) -> u64 {
+        self.time_limit
+            .or_else(|| T::metadata().time_limit)
+            .unwrap_or(DEFAULT_TIME_LIMIT)
+    }
+
+    fn package<T: ProblemSpec<T>>(
+        &self,
+        base_folder: &Path,
+        tests: Vec<export::TestFile>,
+        subtasks: Vec<Subtask>,
+    ) -> Package {
+        let metadata = T::metadata();
+        let slug = self
+            .slug
+            .clone()
+            .or(metadata.slug)
+            .unwrap_or_else(|| export::default_slug(base_folder));
+        let total_score = metadata.total_score.or_else(|| {
+            (!subtasks.is_empty())
+                .then(|| subtasks.iter().map(|subtask| subtask.score as u32).sum())
+        });
+        Package {
+            title: metadata.title.unwrap_or_else(|| slug.clone()),
+            slug,
+            seed: self.seed,
+            tests,
+            subtasks,
+            multiple_testcases: T::multiple_test_case_config().is_some(),
+            time_limit: self.time_limit::<T>(),
+            memory_limit: self
+                .memory_limit
+                .or(metadata.memory_limit)
+                .unwrap_or(DEFAULT_MEMORY_LIMIT),
+            total_score,
+            checker: self.checker.clone().or(metadata.checker),
+            naming: self.naming.clone().or(T::naming_config()),
+        }
+    }
+
+    /// `None` without a solution to run.
+    fn cache(&self, target: &Path) -> Result<Option<Cache>, std::io::Error> {
+        self.solution
+            .as_ref()
+            .map(|solution| Cache::new(target, solution, !self.no_cache))
+            .transpose()
+    }
+
+    fn export(
+        &self,
+        staging: StagingFolder,
+        store: Store,
+        package: &Package,
+    ) -> Result<(), std::io::Error> {
+        export::write(self.format, staging.path(), package)?;
+        store.remove()?;
+        let target = staging.target().to_path_buf();
+        staging.commit()?;
+        if self.zip {
+            export::zip_folder(&target)?;
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum RunnerError {
     #[error("Generate Input/Output Error")]
     GenerateInputOutputError(#[from] GenerateInputOutputError),
     #[error("Generate Sample Testcase Error")]
     GenerateSampleTestCaseError(#[from] GenerateSampleTestCaseError),
-    #[error("IO Error")]
+    #[error("IO Error: {0}")]
     IOError(#[from] std::io::Error),
+    #[error("Subtask scores add up to {found}, expected a total score of {expected}")]
+    ScoreMismatch { expected: u32, found: u32 },
+}
+
+fn print_violations(violations: &[ConstraintViolation]) {
+    let rows: Vec<[String; 3]> = violations
+        .iter()
+        .map(|violation| {
+            [
+                violation
+                    .subtask
+                    .map_or("-".to_string(), |subtask| (subtask + 1).to_string()),
+                violation
+                    .testcase
+                    .map_or("all".to_string(), |testcase| (testcase + 1).to_string()),
+                violation.message.clone(),
+            ]
+        })
+        .collect();
+    let header = ["Subtask", "Testcase", "Constraint"];
+    let width = |column: usize| {
+        rows.iter()
+            .map(|row| row[column].len())
+            .chain(std::iter::once(header[column].len()))
+            .max()
+            .unwrap()
+    };
+    let (subtask_width, testcase_width) = (width(0), width(1));
+
+    println!("    * {} constraint violation(s)", violations.len());
+    println!(
+        "      {:<w0$} | {:<w1$} | {}",
+        header[0],
+        header[1],
+        header[2],
+        w0 = subtask_width,
+        w1 = testcase_width
+    );
+    for row in &rows {
+        println!(
+            "      {:<w0$} | {:<w1$} | {}",
+            row[0],
+            row[1],
+            row[2],
+            w0 = subtask_width,
+            w1 = testcase_width
+        );
+    }
 }
 
-pub fn run_singletask<T>(
-    base_folder: &str,
-    solution_command: Option<&str>,
-    seed: u64,
-) -> Result<(), RunnerError>
+fn subtasks<T>() -> Vec<Subtask>
+where
+    T: MultitaskProblemSpec<T>,
+{
+    [T::subtask_1(), T::subtask_2(), T::subtask_3()]
+        .iter()
+        .enumerate()
+        .filter_map(|(index, config)| {
+            config.as_ref().map(|config| Subtask {
+                index,
+                score: config.score,
+            })
+        })
+        .collect()
+}
+
+/// The spec's total score must match its subtask scores. Single-task problems have no subtask
+/// scores to check it against.
+fn check_total_score<T>(subtasks: &[Subtask]) -> Result<(), RunnerError>
+where
+    T: ProblemSpec<T>,
+{
+    match T::metadata().total_score {
+        Some(expected) => {
+            let found = subtasks.iter().map(|subtask| subtask.score as u32).sum();
+            if found == expected {
+                Ok(())
+            } else {
+                Err(RunnerError::ScoreMismatch { expected, found })
+            }
+        }
+        None => Ok(()),
+    }
+}
+
+pub fn run_singletask<T>(config: &GenerateConfig) -> Result<(), RunnerError>
 where
     T: SingletaskTestSpec<T> + ProblemSpec<T>,
 {
-    let base_folder = prepare_folder(base_folder)?;
+    let staging = StagingFolder::new(&config.output, config.force)?;
+    let store = Store::new(staging.path())?;
+    let cache = config.cache(staging.target())?;
+    let mut tests = Vec::new();
 
     println!("[ SAMPLE TEST CASES ]");
-    match sample::generate::<T>(base_folder, solution_command) {
+    match sample::generate::<T>(config.solution.as_deref(), &store, &mut tests) {
         Ok(_) => Ok(()),
         Err(err) => {
             println!("  ❌");
             match &err {
                 GenerateSampleTestCaseError::ConstraintsError(errors) => {
                     for error in &errors.messages {
-                        println!("    * Expected: {}", error);
+                        println!("    * {}", error);
                     }
                 }
                 GenerateSampleTestCaseError::IOError(error) => {
@@ -61,14 +253,14 @@ where
 
     println!();
     println!("[ OFFICIAL TEST CASES ]");
-    match testcase::generate::<T>(base_folder, solution_command, seed) {
+    match testcase::generate::<T>(config, cache.as_ref(), &store, &mut tests) {
         Ok(_) => Ok(()),
         Err(err) => {
             println!("  ❌");
             match &err {
                 GenerateInputOutputError::ConstraintsError(errors) => {
                     for error in &errors.messages {
-                        println!("    * Expected: {}", error);
+                        println!("    * {}", error);
                     }
                 }
                 GenerateInputOutputError::OutputFormatError(error) => {
@@ -77,32 +269,43 @@ where
                 GenerateInputOutputError::IOError(error) => {
                     println!("    * IO error: {}", error);
                 }
+                GenerateInputOutputError::ConstraintViolations(violations) => {
+                    print_violations(violations);
+                }
             }
-            Err(RunnerError::GenerateInputOutputError(err))
+            Err(err)
         }
     }?;
+    // Only a run where every test passed gets here: a failed one drops the staging folder and
+    // leaves the previous output as it was.
+    let package = config.package::<T>(staging.target(), tests, Vec::new());
+    config.export(staging, store, &package)?;
+    if let Some(cache) = &cache {
+        cache.retain(package.tests.iter().map(|test| test.input.sha256.as_str()))?;
+    }
     Ok(())
 }
 
-pub fn run_multitask<T>(
-    base_folder: &str,
-    solution_command: Option<&str>,
-    seed: u64,
-) -> Result<(), RunnerError>
+pub fn run_multitask<T>(config: &GenerateConfig) -> Result<(), RunnerError>
 where
     T: MultitaskTestSpec<T> + ProblemSpec<T> + MultitaskProblemSpec<T>,
 {
-    let base_folder = prepare_folder(base_folder)?;
+    let subtasks = subtasks::<T>();
+    check_total_score::<T>(&subtasks)?;
+    let staging = StagingFolder::new(&config.output, config.force)?;
+    let store = Store::new(staging.path())?;
+    let cache = config.cache(staging.target())?;
+    let mut tests = Vec::new();
 
     println!("[ SAMPLE TEST CASES ]");
-    match sample::generate_multitask::<T>(base_folder, solution_command) {
+    match sample::generate_multitask::<T>(config.solution.as_deref(), &store, &mut tests) {
         Ok(_) => Ok(()),
         Err(err) => {
             println!("  ❌");
             match &err {
                 GenerateSampleTestCaseError::ConstraintsError(errors) => {
                     for error in &errors.messages {
-                        println!("    * Expected: {}", error);
+                        println!("    * {}", error);
                     }
                 }
                 GenerateSampleTestCaseError::IOError(error) => {
@@ -122,14 +325,14 @@ where
 
     println!();
     println!("[ OFFICIAL TEST CASES ]");
-    match testcase::generate_multitask::<T>(base_folder, solution_command, seed) {
+    match testcase::generate_multitask::<T>(config, cache.as_ref(), &store, &mut tests) {
         Ok(_) => Ok(()),
         Err(err) => {
             println!("  ❌");
             match &err {
                 GenerateInputOutputError::ConstraintsError(errors) => {
                     for error in &errors.messages {
-                        println!("    * Expected: {}", error);
+                        println!("    * {}", error);
                     }
                 }
                 GenerateInputOutputError::OutputFormatError(error) => {
@@ -138,9 +341,176 @@ where
                 GenerateInputOutputError::IOError(error) => {
                     println!("    * IO error: {}", error);
                 }
+                GenerateInputOutputError::ConstraintViolations(violations) => {
+                    print_violations(violations);
+                }
             }
-            Err(RunnerError::GenerateInputOutputError(err))
+            Err(err)
         }
     }?;
+    // Only a run where every test passed gets here: a failed one drops the staging folder and
+    // leaves the previous output as it was.
+    let package = config.package::<T>(staging.target(), tests, subtasks);
+    config.export(staging, store, &package)?;
+    if let Some(cache) = &cache {
+        cache.retain(package.tests.iter().map(|test| test.input.sha256.as_str()))?;
+    }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        problemspec::spec::{
+            ConstraintsError, IOElement, IOFormat, LineElement, ProblemMetadata, Scalar,
+            SubtaskConfig,
+        },
+        runner::test_util::config,
+        testspec::spec::TestCases,
+        CONS, LINE, LS,
+    };
+
+    struct Spec {
+        n: i64,
+    }
+
+    impl ProblemSpec<Spec> for Spec {
+        fn input_format(&self) -> IOFormat {
+            vec![LINE!(LS!(self.n))]
+        }
+
+        fn output_format(&self) -> IOFormat {
+            Vec::new()
+        }
+
+        fn constraints(&self) -> Result<(), ConstraintsError> {
+            CONS!(self.n > 0)
+        }
+
+        fn metadata() -> ProblemMetadata {
+            ProblemMetadata {
+                total_score: Some(100),
+                ..ProblemMetadata::default()
+            }
+        }
+    }
+
+    impl MultitaskProblemSpec<Spec> for Spec {
+        fn subtask_1() -> Option<SubtaskConfig<Spec>> {
+            Some(SubtaskConfig {
+                score: 40,
+                constraints: |_| Ok(()),
+            })
+        }
+
+        fn subtask_2() -> Option<SubtaskConfig<Spec>> {
+            Some(SubtaskConfig {
+                score: 50,
+                constraints: |_| Ok(()),
+            })
+        }
+    }
+
+    impl MultitaskTestSpec<Spec> for Spec {
+        fn sample_test_cases() -> Vec<Spec> {
+            vec![Spec { n: 1 }]
+        }
+    }
+
+    /// The second test case violates the constraints.
+    impl SingletaskTestSpec<Spec> for Spec {
+        fn sample_test_cases() -> Vec<Spec> {
+            vec![Spec { n: 1 }]
+        }
+
+        fn test_cases(mut tests: TestCases<Spec>) -> TestCases<Spec> {
+            tests.add(|_| Spec { n: 2 });
+            tests.add(|_| Spec { n: 0 });
+            tests
+        }
+    }
+
+    #[test]
+    fn test_failed_run_leaves_previous_output() {
+        let folder = std::env::temp_dir().join(format!("testgen-failed-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&folder);
+        let output = folder.join("tc");
+        std::fs::create_dir_all(&output).unwrap();
+        io::write_file(
+            "{\n  \"generator\": \"testgen\"\n}\n",
+            &output.join(export::MANIFEST),
+        )
+        .unwrap();
+        io::write_file("previous\n", &output.join("1.in")).unwrap();
+
+        for keep_going in [false, true] {
+            let config = GenerateConfig {
+                keep_going,
+                ..config(&output)
+            };
+            assert!(run_singletask::<Spec>(&config).is_err());
+            assert_eq!(
+                std::fs::read_to_string(output.join("1.in")).unwrap(),
+                "previous\n"
+            );
+            assert!(!output.join("sample_1.in").exists());
+            assert!(!folder.join(".tc.testgen-tmp").exists());
+        }
+        std::fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[test]
+    fn test_failed_run_keeps_cache() {
+        let folder =
+            std::env::temp_dir().join(format!("testgen-keep-cache-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&folder);
+        let output = folder.join("tc");
+        let execution = executor::Execution {
+            output: String::new(),
+            runtime: std::time::Duration::from_millis(1),
+        };
+        let input = export::sha256(b"previous\n");
+        Cache::new(&output, "true", true)
+            .unwrap()
+            .put(&input, &execution)
+            .unwrap();
+
+        let config = GenerateConfig {
+            solution: Some("true".to_string()),
+            ..config(&output)
+        };
+        assert!(run_singletask::<Spec>(&config).is_err());
+        assert!(Cache::new(&output, "true", true)
+            .unwrap()
+            .get(&input)
+            .is_some());
+        std::fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[test]
+    fn test_check_total_score() {
+        let subtask = |index, score| Subtask { index, score };
+        assert!(check_total_score::<Spec>(&[subtask(0, 40), subtask(1, 60)]).is_ok());
+        assert!(matches!(
+            check_total_score::<Spec>(&[subtask(0, 40)]),
+            Err(RunnerError::ScoreMismatch {
+                expected: 100,
+                found: 40
+            })
+        ));
+    }
+
+    #[test]
+    fn test_score_mismatch_stops_before_writing() {
+        let output = std::env::temp_dir().join(format!("testgen-score-{}", std::process::id()));
+        assert!(matches!(
+            run_multitask::<Spec>(&config(&output)),
+            Err(RunnerError::ScoreMismatch {
+                expected: 100,
+                found: 90
+            })
+        ));
+        assert!(!output.exists());
+    }
+}
@@ -1,11 +1,9 @@
-use std::path::Path;
-
 use crate::{
     problemspec::{
         generator::Generator,
         spec::{ConstraintsError, MultitaskProblemSpec, ProblemSpec},
     },
-    runner::{executor, io::write_file},
+    runner::{executor, export::TestFile, io::Store},
     testspec::spec::{MultitaskTestSpec, SingletaskTestSpec},
 };
 use thiserror::Error;
@@ -22,9 +20,10 @@ pub enum GenerateSampleTestCaseError {
 //TODO: refactor, extract common logics
 //TODO: unit test
 fn _generate<T>(
-    base_folder: &Path,
     specs: Vec<T>,
     solution_command: Option<&str>,
+    store: &Store,
+    tests: &mut Vec<TestFile>,
 ) -> Result<(), GenerateSampleTestCaseError>
 where
     T: ProblemSpec<T>,
@@ -38,12 +37,13 @@ where
             inputs.push_str(format!("{}\n", t).as_str());
             for (i, spec) in specs.iter().enumerate() {
                 println!("Sample case #{}...", i + 1);
-                spec.constraints()?;
+                spec.constraints()
+                    .map_err(|error| error.with_context(&format!("Sample case #{}", i + 1)))?;
 
                 let input = spec.input_format().generate().unwrap();
                 inputs.push_str(&input);
                 if i != specs.len() - 1 {
-                    inputs.push_str("\n");
+                    inputs.push('\n');
                 }
 
                 if let Some(output_prefix) = &multi_test_config.output_prefix {
@@ -52,22 +52,18 @@ where
                 let output = spec.output_format().generate().unwrap();
                 outputs.push_str(&output);
                 if i != specs.len() - 1 {
-                    outputs.push_str("\n");
+                    outputs.push('\n');
                 }
             }
             let constraints = multi_test_config.constraints;
-            constraints(t)?;
-
-            let input_path = base_folder.join(format!("sample_{}.in", 1));
-            write_file(&inputs, &input_path)?;
+            ConstraintsError::collect(vec![constraints(t), T::file_constraints(&specs)])
+                .map_err(|error| error.with_context("Sample cases"))?;
 
             if let Some(solution_command) = &solution_command {
-                let observed_output = executor::execute(&solution_command, &inputs);
+                let observed_output = executor::execute(solution_command, &inputs)?.output;
                 //TODO: zip only takes the lower len, check the remaining
-                for (expected_output, output) in outputs
-                    .split("\n")
-                    .into_iter()
-                    .zip(observed_output.split("\n").into_iter())
+                for (expected_output, output) in
+                    outputs.split('\n').zip(observed_output.split('\n'))
                 {
                     if expected_output != output {
                         return Err(GenerateSampleTestCaseError::SampleOutputMismatch(
@@ -78,29 +74,34 @@ where
                 }
             }
 
-            let output_path = base_folder.join(format!("sample_{}.out", 1));
-            write_file(&outputs, &output_path)?;
+            tests.push(TestFile {
+                subtask: None,
+                index: 0,
+                sample: true,
+                input: store.write(&inputs)?,
+                output: Some(store.write(&outputs)?),
+                seed: None,
+                runtime: None,
+            });
             Ok(())
         }
         None => {
             for (i, spec) in specs.iter().enumerate() {
                 println!("Sample case #{}...", i + 1);
-                spec.constraints()?;
+                ConstraintsError::collect(vec![
+                    spec.constraints(),
+                    T::file_constraints(std::slice::from_ref(spec)),
+                ])
+                .map_err(|error| error.with_context(&format!("Sample case #{}", i + 1)))?;
 
                 let input = spec.input_format().generate().unwrap();
-                let input_path = base_folder.join(format!("sample_{}.in", i + 1));
-                write_file(&input, &input_path)?;
-
                 let output = spec.output_format().generate().unwrap();
-                let output_path = base_folder.join(format!("sample_{}.out", i + 1));
 
                 if let Some(solution_command) = &solution_command {
-                    let observed_output = executor::execute(&solution_command, &input);
+                    let observed_output = executor::execute(solution_command, &input)?.output;
                     //TODO: zip only takes the lower len, check the remaining
-                    for (expected_output, output) in output
-                        .split("\n")
-                        .into_iter()
-                        .zip(observed_output.split("\n").into_iter())
+                    for (expected_output, output) in
+                        output.split('\n').zip(observed_output.split('\n'))
                     {
                         if expected_output != output {
                             return Err(GenerateSampleTestCaseError::SampleOutputMismatch(
@@ -111,7 +112,15 @@ where
                     }
                 }
 
-                write_file(&output, &output_path)?;
+                tests.push(TestFile {
+                    subtask: None,
+                    index: i,
+                    sample: true,
+                    input: store.write(&input)?,
+                    output: Some(store.write(&output)?),
+                    seed: None,
+                    runtime: None,
+                });
             }
             Ok(())
         }
@@ -119,23 +128,25 @@ where
 }
 
 pub fn generate<T>(
-    base_folder: &Path,
     solution_command: Option<&str>,
+    store: &Store,
+    tests: &mut Vec<TestFile>,
 ) -> Result<(), GenerateSampleTestCaseError>
 where
     T: ProblemSpec<T> + SingletaskTestSpec<T>,
 {
     let specs = T::sample_test_cases();
-    _generate(base_folder, specs, solution_command)
+    _generate(specs, solution_command, store, tests)
 }
 
 pub fn generate_multitask<T>(
-    base_folder: &Path,
     solution_command: Option<&str>,
+    store: &Store,
+    tests: &mut Vec<TestFile>,
 ) -> Result<(), GenerateSampleTestCaseError>
 where
     T: ProblemSpec<T> + MultitaskProblemSpec<T> + MultitaskTestSpec<T>,
 {
     let specs = T::sample_test_cases();
-    _generate(base_folder, specs, solution_command)
+    _generate(specs, solution_command, store, tests)
 }
@@ -0,0 +1,61 @@
+//! Factories shared by the runner's tests, so adding a field means editing one place.
+
+use std::path::Path;
+
+use crate::problemspec::spec::NamingConfig;
+
+use super::{
+    export::{Format, Package, Subtask, TestFile},
+    io::Stored,
+    GenerateConfig,
+};
+
+/// A test file with an empty input, and no output, seed or runtime.
+pub fn test_file(subtask: Option<usize>, index: usize, sample: bool) -> TestFile {
+    TestFile {
+        subtask,
+        index,
+        sample,
+        input: Stored::default(),
+        output: None,
+        seed: None,
+        runtime: None,
+    }
+}
+
+/// A problem called "sum" with the default limits, checker and naming.
+pub fn package(tests: Vec<TestFile>, subtasks: Vec<Subtask>) -> Package {
+    Package {
+        slug: "sum".to_string(),
+        title: "sum".to_string(),
+        seed: 0,
+        tests,
+        subtasks,
+        multiple_testcases: false,
+        time_limit: 1000,
+        memory_limit: 256,
+        total_score: None,
+        checker: None,
+        naming: NamingConfig::default(),
+    }
+}
+
+/// `generate --output <output>` with every other option left out.
+pub fn config(output: &Path) -> GenerateConfig {
+    GenerateConfig {
+        output: output.to_string_lossy().into_owned(),
+        solution: None,
+        seed: 0,
+        keep_going: false,
+        format: Format::Flat,
+        time_limit: None,
+        memory_limit: None,
+        checker: None,
+        zip: false,
+        jobs: 1,
+        no_cache: false,
+        force: false,
+        slug: None,
+        naming: NamingConfig::default(),
+    }
+}
@@ -1,14 +1,20 @@
-use std::path::Path;
+use std::fs::read_to_string;
 
 use crate::{
     problemspec::{
         generator::Generator,
         spec::{ConstraintsError, MultipleTestcaseConfig, MultitaskProblemSpec, ProblemSpec},
     },
-    runner::{executor, io::write_file},
+    runner::{
+        cache::Cache,
+        executor::{self, Execution},
+        export::TestFile,
+        io::{Store, Stored},
+        GenerateConfig,
+    },
     testspec::{
         random::Random,
-        spec::{MultitaskTestSpec, SingletaskTestSpec},
+        spec::{MultitaskTestSpec, SingletaskTestSpec, TestCases},
     },
 };
 use thiserror::Error;
@@ -18,7 +24,7 @@ fn check_output(
     output: &str,
 ) -> Result<(), GenerateInputOutputError> {
     if let Some(output_prefix) = &multi_test_config.output_prefix {
-        let lines = output.split("\n");
+        let lines = output.split('\n');
         for (i, line) in lines.enumerate() {
             let output_prefix = output_prefix.replace("{}", &(i + 1).to_string());
             if !line.is_empty() && !line.starts_with(&output_prefix) {
@@ -32,6 +38,7 @@ fn check_output(
     Ok(())
 }
 #[derive(Debug, Error)]
+#[allow(clippy::enum_variant_names)]
 pub enum GenerateInputOutputError {
     #[error("Constraints error")]
     ConstraintsError(#[from] ConstraintsError),
@@ -39,143 +46,543 @@ pub enum GenerateInputOutputError {
     OutputFormatError(String),
     #[error("IO Error")]
     IOError(#[from] std::io::Error),
+    #[error("Constraint violations")]
+    ConstraintViolations(Vec<ConstraintViolation>),
+}
+
+/// A constraint violated by a test case, collected when generation keeps going past failures.
+#[derive(Debug)]
+pub struct ConstraintViolation {
+    pub subtask: Option<usize>,
+    /// `None` when the constraint applies to the whole file, e.g. the number of test cases.
+    pub testcase: Option<usize>,
+    pub message: String,
+}
+
+type SubtaskConstraints<T> = fn(&T) -> Result<(), ConstraintsError>;
+
+fn context(subtask: Option<usize>, testcase: Option<usize>) -> String {
+    match (subtask, testcase) {
+        (Some(subtask), Some(testcase)) => {
+            format!("Subtask #{}, Testcase #{}", subtask + 1, testcase + 1)
+        }
+        (Some(subtask), None) => format!("Subtask #{}", subtask + 1),
+        (None, Some(testcase)) => format!("Testcase #{}", testcase + 1),
+        (None, None) => "Testcases".to_string(),
+    }
+}
+
+fn check_constraints<T>(
+    spec: &T,
+    subtask_constraints: Option<SubtaskConstraints<T>>,
+) -> Result<(), ConstraintsError>
+where
+    T: ProblemSpec<T>,
+{
+    ConstraintsError::collect(vec![
+        subtask_constraints.map_or(Ok(()), |subtask_constraints| subtask_constraints(spec)),
+        spec.constraints(),
+    ])
+}
+
+/// Returns whether the test case satisfied its constraints. Violations are collected when
+/// `keep_going` is set, and returned as an error otherwise.
+fn record_violations(
+    result: Result<(), ConstraintsError>,
+    subtask: Option<usize>,
+    testcase: Option<usize>,
+    keep_going: bool,
+    violations: &mut Vec<ConstraintViolation>,
+) -> Result<bool, ConstraintsError> {
+    match result {
+        Ok(()) => Ok(true),
+        Err(error) if keep_going => {
+            violations.extend(
+                error
+                    .messages
+                    .into_iter()
+                    .map(|message| ConstraintViolation {
+                        subtask,
+                        testcase,
+                        message,
+                    }),
+            );
+            Ok(false)
+        }
+        Err(error) => Err(error.with_context(&context(subtask, testcase))),
+    }
 }
 
 fn _generate<T>(
-    base_folder: &Path,
-    specs: &Vec<T>,
-    multi_test_config: Option<&MultipleTestcaseConfig>,
-    solution_command: Option<&str>,
-    name_prefix: Option<&str>,
-    subtask_constraints: Option<fn(&T) -> Result<(), ConstraintsError>>,
+    cases: &TestCases<T>,
+    config: &GenerateConfig,
+    subtask: Option<usize>,
+    subtask_constraints: Option<SubtaskConstraints<T>>,
+    violations: &mut Vec<ConstraintViolation>,
+    store: &Store,
+    tests: &mut Vec<TestFile>,
 ) -> Result<(), GenerateInputOutputError>
 where
     T: ProblemSpec<T>,
 {
-    match multi_test_config {
+    let specs = cases.specs();
+    match T::multiple_test_case_config() {
         Some(multi_test_config) => {
             let mut inputs = String::new();
+            let mut valid = true;
             let t = specs.len();
             inputs.push_str(format!("{}\n", t).as_str());
             for (i, spec) in specs.iter().enumerate() {
                 println!("Testcase #{}...", i + 1);
-                if let Some(subtask_constraints) = subtask_constraints {
-                    subtask_constraints(&spec)?;
-                }
-                spec.constraints()?;
+                valid &= record_violations(
+                    check_constraints(spec, subtask_constraints),
+                    subtask,
+                    Some(i),
+                    config.keep_going,
+                    violations,
+                )?;
 
                 let input = spec.input_format().generate().unwrap();
                 inputs.push_str(&input);
                 if i != specs.len() - 1 {
-                    inputs.push_str("\n");
+                    inputs.push('\n');
                 }
             }
             let constraints = multi_test_config.constraints;
-            constraints(t)?;
-
-            let file_name = if let Some(name_prefix) = name_prefix {
-                format!("{}", name_prefix)
-            } else {
-                format!("{}", 1)
-            };
-
-            let input_path = base_folder.join(format!("{}.in", file_name));
-            write_file(&inputs, &input_path)?;
-
-            if let Some(solution_command) = &solution_command {
-                let output = executor::execute(&solution_command, &inputs);
-
-                check_output(&multi_test_config, &output)?;
-
-                let output_path = base_folder.join(format!("{}.out", file_name));
-                write_file(&output, &output_path)?;
+            valid &= record_violations(
+                ConstraintsError::collect(vec![constraints(t), T::file_constraints(specs)]),
+                subtask,
+                None,
+                config.keep_going,
+                violations,
+            )?;
+            if !valid {
+                return Ok(());
             }
+
+            tests.push(TestFile {
+                subtask,
+                index: 0,
+                sample: false,
+                input: store.write(&inputs)?,
+                output: None,
+                // Test case `i` of the file is generated from stream `i` of this seed.
+                seed: Some(cases.seed()),
+                runtime: None,
+            });
             Ok(())
         }
         None => {
             for (i, spec) in specs.iter().enumerate() {
                 println!("Testcase #{}...", i + 1);
-                if let Some(subtask_constraints) = subtask_constraints {
-                    subtask_constraints(&spec)?;
+                if !record_violations(
+                    ConstraintsError::collect(vec![
+                        check_constraints(spec, subtask_constraints),
+                        T::file_constraints(std::slice::from_ref(spec)),
+                    ]),
+                    subtask,
+                    Some(i),
+                    config.keep_going,
+                    violations,
+                )? {
+                    continue;
                 }
-                spec.constraints()?;
 
-                let file_name = if let Some(name_prefix) = name_prefix {
-                    format!("{}_{}", name_prefix, i + 1)
-                } else {
-                    format!("{}", i + 1)
-                };
                 let input = spec.input_format().generate().unwrap();
-                let input_path = base_folder.join(format!("{}.in", file_name));
-                write_file(&input, &input_path)?;
+                tests.push(TestFile {
+                    subtask,
+                    index: i,
+                    sample: false,
+                    input: store.write(&input)?,
+                    output: None,
+                    seed: Some(cases.seeds()[i]),
+                    runtime: None,
+                });
+            }
+            Ok(())
+        }
+    }
+}
 
-                if let Some(solution_command) = &solution_command {
-                    let output = executor::execute(&solution_command, &input);
+fn violations_result(violations: Vec<ConstraintViolation>) -> Result<(), GenerateInputOutputError> {
+    if violations.is_empty() {
+        Ok(())
+    } else {
+        Err(GenerateInputOutputError::ConstraintViolations(violations))
+    }
+}
 
-                    let output_path = base_folder.join(format!("{}.out", file_name));
-                    write_file(&output, &output_path)?;
-                }
+/// Runs the solution on the tests, reusing the outputs cached for inputs it already solved. The
+/// remaining tests run `config.jobs` at a time, and are then checked and reported in order.
+fn solve<T>(
+    config: &GenerateConfig,
+    cache: Option<&Cache>,
+    store: &Store,
+    tests: &mut [TestFile],
+) -> Result<(), GenerateInputOutputError>
+where
+    T: ProblemSpec<T>,
+{
+    let solution_command = match &config.solution {
+        Some(solution_command) => solution_command,
+        None => return Ok(()),
+    };
+    let time_limit = config.time_limit::<T>();
+    let multi_test_config = T::multiple_test_case_config();
+    let mut reused = 0;
+    let mut pending = Vec::new();
+    let mut files = Vec::new();
+    for test in tests.iter_mut() {
+        match cache.and_then(|cache| cache.get(&test.input.sha256)) {
+            Some(execution) => {
+                test.output = Some(store.write(&execution.output)?);
+                test.runtime = Some(execution.runtime);
+                reused += 1;
             }
-            Ok(())
+            None => {
+                files.push((test.input.path.clone(), store.reserve()));
+                pending.push(test);
+            }
+        }
+    }
+    let runtimes = executor::execute_all(solution_command, &files, config.jobs)?;
+
+    for ((test, (_, output)), runtime) in pending.into_iter().zip(files).zip(runtimes) {
+        if runtime.as_millis() > time_limit as u128 {
+            println!(
+                "  ⚠ {}: solution took {} ms, over the time limit of {} ms",
+                context(
+                    test.subtask,
+                    multi_test_config.is_none().then_some(test.index)
+                ),
+                runtime.as_millis(),
+                time_limit
+            );
+        }
+        let execution = Execution {
+            output: read_to_string(&output)?,
+            runtime,
+        };
+        if let Some(multi_test_config) = &multi_test_config {
+            check_output(multi_test_config, &execution.output)?;
+        }
+        if let Some(cache) = cache {
+            cache.put(&test.input.sha256, &execution)?;
         }
+        test.output = Some(Stored::new(output, &execution.output));
+        test.runtime = Some(runtime);
     }
+    if reused > 0 {
+        println!("  {} output(s) reused from the cache", reused);
+    }
+    Ok(())
+}
+
+/// Whether the solution should still run after generating the inputs: not after an error, but
+/// after violations collected by `keep_going`, so that the tests which passed are checked too.
+fn should_solve(result: &Result<(), GenerateInputOutputError>) -> bool {
+    matches!(
+        result,
+        Ok(()) | Err(GenerateInputOutputError::ConstraintViolations(_))
+    )
 }
 
+/// Generates the tests, then runs the solution on those that satisfied their constraints.
 pub fn generate<T>(
-    base_folder: &Path,
-    solution_command: Option<&str>,
-    seed: u64,
+    config: &GenerateConfig,
+    cache: Option<&Cache>,
+    store: &Store,
+    tests: &mut Vec<TestFile>,
 ) -> Result<(), GenerateInputOutputError>
 where
     T: ProblemSpec<T> + SingletaskTestSpec<T>,
 {
-    let mut random = Random::new(seed);
-    let specs = T::test_cases(&mut random);
-    let multi_test_config = T::multiple_test_case_config();
-    _generate(
-        base_folder,
-        &specs,
-        multi_test_config.as_ref(),
-        solution_command,
-        None,
-        None,
-    )
+    let start = tests.len();
+    let result = generate_inputs::<T>(config, store, tests);
+    if should_solve(&result) {
+        solve::<T>(config, cache, store, &mut tests[start..])?;
+    }
+    result
 }
 
 pub fn generate_multitask<T>(
-    base_folder: &Path,
-    solution_command: Option<&str>,
-    seed: u64,
+    config: &GenerateConfig,
+    cache: Option<&Cache>,
+    store: &Store,
+    tests: &mut Vec<TestFile>,
 ) -> Result<(), GenerateInputOutputError>
 where
     T: ProblemSpec<T> + MultitaskTestSpec<T> + MultitaskProblemSpec<T>,
 {
-    let mut random = Random::new(seed);
+    let start = tests.len();
+    let result = generate_multitask_inputs::<T>(config, store, tests);
+    if should_solve(&result) {
+        solve::<T>(config, cache, store, &mut tests[start..])?;
+    }
+    result
+}
+
+fn generate_inputs<T>(
+    config: &GenerateConfig,
+    store: &Store,
+    tests: &mut Vec<TestFile>,
+) -> Result<(), GenerateInputOutputError>
+where
+    T: ProblemSpec<T> + SingletaskTestSpec<T>,
+{
+    let cases = T::test_cases(TestCases::new(Random::new(config.seed)));
+    let mut violations = Vec::new();
+    _generate(&cases, config, None, None, &mut violations, store, tests)?;
+    violations_result(violations)
+}
+
+fn generate_multitask_inputs<T>(
+    config: &GenerateConfig,
+    store: &Store,
+    tests: &mut Vec<TestFile>,
+) -> Result<(), GenerateInputOutputError>
+where
+    T: ProblemSpec<T> + MultitaskTestSpec<T> + MultitaskProblemSpec<T>,
+{
+    // Every subtask draws from its own stream, and every test case from its own stream of the
+    // subtask's, so editing one generator leaves every other test case unchanged.
+    let random = Random::new(config.seed);
     let configs = [T::subtask_1(), T::subtask_2(), T::subtask_3()];
     let specs = [
-        T::test_cases_subtask_1(&mut random),
-        T::test_cases_subtask_2(&mut random),
-        T::test_cases_subtask_3(&mut random),
+        T::test_cases_subtask_1(TestCases::new(random.derive(1))),
+        T::test_cases_subtask_2(TestCases::new(random.derive(2))),
+        T::test_cases_subtask_3(TestCases::new(random.derive(3))),
     ];
-    let multi_test_config = T::multiple_test_case_config();
+    let mut violations = Vec::new();
 
-    for (i, (spec, config)) in specs.iter().zip(configs.iter()).enumerate() {
-        match (spec, config) {
-            (Some(spec), Some(config)) => {
-                let subtask_constraints = config.constraints;
-                println!("Subtask #{}...", i + 1);
-
-                _generate(
-                    base_folder,
-                    spec,
-                    multi_test_config.as_ref(),
-                    solution_command,
-                    Some(i.to_string().as_str()),
-                    Some(subtask_constraints),
-                )?;
+    for (i, (spec, subtask_config)) in specs.iter().zip(configs.iter()).enumerate() {
+        if let (Some(spec), Some(subtask_config)) = (spec, subtask_config) {
+            let subtask_constraints = subtask_config.constraints;
+            println!("Subtask #{}...", i + 1);
+
+            _generate(
+                spec,
+                config,
+                Some(i),
+                Some(subtask_constraints),
+                &mut violations,
+                store,
+                tests,
+            )?;
+        }
+    }
+    violations_result(violations)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::{
+        problemspec::spec::{IOElement, IOFormat, LineElement, Scalar, SubtaskConfig},
+        runner::test_util,
+        CONS, IN_RANGE, LINE, LS,
+    };
+
+    struct Spec {
+        n: i64,
+    }
+
+    impl ProblemSpec<Spec> for Spec {
+        fn input_format(&self) -> IOFormat {
+            vec![LINE!(LS!(self.n))]
+        }
+
+        fn output_format(&self) -> IOFormat {
+            Vec::new()
+        }
+
+        fn constraints(&self) -> Result<(), ConstraintsError> {
+            CONS!(IN_RANGE!(self.n, 1, 10))
+        }
+    }
+
+    impl SingletaskTestSpec<Spec> for Spec {
+        fn sample_test_cases() -> Vec<Spec> {
+            Vec::new()
+        }
+
+        fn test_cases(mut tests: TestCases<Spec>) -> TestCases<Spec> {
+            for n in [1, 20, 5, 30] {
+                tests.add(|_| Spec { n });
             }
-            _ => {}
+            tests
+        }
+    }
+
+    /// The first test case of subtask 1 draws `DRAWS` numbers and keeps the last one. Every other
+    /// test case draws one number.
+    struct Multitask<const DRAWS: usize> {
+        n: i64,
+    }
+
+    impl<const DRAWS: usize> ProblemSpec<Multitask<DRAWS>> for Multitask<DRAWS> {
+        fn input_format(&self) -> IOFormat {
+            vec![LINE!(LS!(self.n))]
+        }
+
+        fn output_format(&self) -> IOFormat {
+            Vec::new()
+        }
+
+        fn constraints(&self) -> Result<(), ConstraintsError> {
+            Ok(())
+        }
+    }
+
+    impl<const DRAWS: usize> MultitaskProblemSpec<Multitask<DRAWS>> for Multitask<DRAWS> {
+        fn subtask_1() -> Option<SubtaskConfig<Multitask<DRAWS>>> {
+            Some(SubtaskConfig {
+                score: 40,
+                constraints: |_| Ok(()),
+            })
+        }
+
+        fn subtask_2() -> Option<SubtaskConfig<Multitask<DRAWS>>> {
+            Some(SubtaskConfig {
+                score: 60,
+                constraints: |_| Ok(()),
+            })
+        }
+    }
+
+    impl<const DRAWS: usize> MultitaskTestSpec<Multitask<DRAWS>> for Multitask<DRAWS> {
+        fn sample_test_cases() -> Vec<Multitask<DRAWS>> {
+            Vec::new()
+        }
+
+        fn test_cases_subtask_1(
+            mut tests: TestCases<Multitask<DRAWS>>,
+        ) -> Option<TestCases<Multitask<DRAWS>>> {
+            tests.add(|random| Multitask {
+                n: (0..DRAWS)
+                    .map(|_| random.next_range(1, 1_000_000))
+                    .last()
+                    .unwrap(),
+            });
+            for _ in 0..2 {
+                tests.add(|random| Multitask {
+                    n: random.next_range(1, 1_000_000),
+                });
+            }
+            Some(tests)
+        }
+
+        fn test_cases_subtask_2(
+            mut tests: TestCases<Multitask<DRAWS>>,
+        ) -> Option<TestCases<Multitask<DRAWS>>> {
+            for _ in 0..3 {
+                tests.add(|random| Multitask {
+                    n: random.next_range(1, 1_000_000),
+                });
+            }
+            Some(tests)
+        }
+    }
+
+    /// A store in a fresh scratch folder, removed by the caller.
+    fn store(name: &str) -> (std::path::PathBuf, Store) {
+        let folder =
+            std::env::temp_dir().join(format!("testgen-testcase-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&folder);
+        std::fs::create_dir_all(&folder).unwrap();
+        let store = Store::new(&folder).unwrap();
+        (folder, store)
+    }
+
+    fn config(keep_going: bool) -> GenerateConfig {
+        GenerateConfig {
+            keep_going,
+            ..test_util::config(Path::new("tc"))
+        }
+    }
+
+    #[test]
+    fn test_keep_going_collects_every_violation() {
+        let (folder, store) = store("keep-going");
+        let mut tests = Vec::new();
+        let config = GenerateConfig {
+            solution: Some("cat".to_string()),
+            ..config(true)
+        };
+        let violations = match generate::<Spec>(&config, None, &store, &mut tests) {
+            Err(GenerateInputOutputError::ConstraintViolations(violations)) => violations,
+            result => panic!("expected constraint violations, found {:?}", result),
+        };
+        let failed: Vec<Option<usize>> = violations
+            .iter()
+            .map(|violation| violation.testcase)
+            .collect();
+        assert_eq!(failed, [Some(1), Some(3)]);
+        assert!(violations[0].message.contains("found 20"));
+        assert!(violations[1].message.contains("found 30"));
+
+        let generated: Vec<(usize, String)> = tests
+            .iter()
+            .map(|test| (test.index, read_to_string(&test.input.path).unwrap()))
+            .collect();
+        assert_eq!(generated, [(0, "1".to_string()), (2, "5".to_string())]);
+        assert!(tests.iter().all(|test| test.output.is_some()));
+        std::fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[test]
+    fn test_stops_at_first_violation_without_keep_going() {
+        let (folder, store) = store("stop");
+        let mut tests = Vec::new();
+        let config = GenerateConfig {
+            solution: Some("cat".to_string()),
+            ..config(false)
+        };
+        match generate::<Spec>(&config, None, &store, &mut tests) {
+            Err(GenerateInputOutputError::ConstraintsError(error)) => {
+                assert_eq!(error.messages.len(), 1);
+                assert!(error.messages[0].starts_with("Testcase #2: "));
+            }
+            result => panic!("expected a constraints error, found {:?}", result),
+        }
+        assert_eq!(tests.len(), 1);
+        assert!(tests[0].output.is_none());
+        std::fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[test]
+    fn test_editing_one_test_case_leaves_the_others_unchanged() {
+        /// The subtask, seed and input of every test.
+        fn generated<T>(name: &str) -> Vec<(Option<usize>, u64, String)>
+        where
+            T: ProblemSpec<T> + MultitaskTestSpec<T> + MultitaskProblemSpec<T>,
+        {
+            let (folder, store) = store(name);
+            let mut tests = Vec::new();
+            generate_multitask::<T>(&config(false), None, &store, &mut tests).unwrap();
+            let generated = tests
+                .into_iter()
+                .map(|test| {
+                    let input = read_to_string(test.input.path).unwrap();
+                    (test.subtask, test.seed.unwrap(), input)
+                })
+                .collect();
+            std::fs::remove_dir_all(folder).unwrap();
+            generated
+        }
+
+        let tests = generated::<Multitask<1>>("streams");
+        let edited = generated::<Multitask<5>>("streams-edited");
+        assert_eq!(tests.len(), 6);
+        assert_ne!(edited[0].2, tests[0].2);
+        assert_eq!(edited[1..], tests[1..]);
+
+        // Each test can be generated again on its own from its recorded seed.
+        for (_, seed, input) in &tests {
+            let n = Random::new(*seed).next_range(1, 1_000_000);
+            assert_eq!(*input, n.to_string());
         }
     }
-    Ok(())
 }
@@ -0,0 +1,267 @@
+//! Tests that break common wrong or slow approaches.
+
+use std::collections::HashMap;
+
+use super::{random::Random, string::thue_morse};
+
+/// `std::unordered_map` bucket count GCC 7 and later reach around 10^5 elements.
+pub const GCC_7_BUCKET_PRIME: u64 = 107_897;
+/// `std::unordered_map` bucket count GCC 6 reaches around 10^5 elements.
+pub const GCC_6_BUCKET_PRIME: u64 = 126_271;
+
+/// A permutation of `1..=n` that makes quicksort take quadratic time when it picks the middle
+/// element as pivot, swaps it to the front and partitions the rest in place (Lomuto). An already
+/// sorted array does the same for first-element pivots.
+pub fn anti_quicksort(n: usize) -> Vec<i64> {
+    // Simulate the sort on positions, always giving the pivot the smallest value left so that
+    // every partition step only removes the pivot.
+    let mut positions: Vec<usize> = (0..n).collect();
+    let mut values = vec![0; n];
+    for (value, left) in (0..n).enumerate() {
+        let middle = left + (n - 1 - left) / 2;
+        values[positions[middle]] = value as i64 + 1;
+        positions.swap(left, middle);
+    }
+    values
+}
+
+/// Polynomial hash `s[0] * base^(len-1) + ... + s[len-1]` modulo `modulus`.
+pub fn polynomial_hash(s: &str, base: u64, modulus: u64) -> u64 {
+    s.bytes().fold(0, |hash, c| {
+        ((hash as u128 * base as u128 + c as u128) % modulus as u128) as u64
+    })
+}
+
+/// Two different strings of length `2^k` over `ab` with the same polynomial hash modulo `2^64`
+/// (that is, with wrapping `u64` arithmetic) for every odd base, as long as `k >= 11`.
+pub fn anti_overflow_hash_pair(k: u32) -> (String, String) {
+    let s = thue_morse(1 << k);
+    let t = s
+        .chars()
+        .map(|c| if c == 'a' { 'b' } else { 'a' })
+        .collect();
+    (s, t)
+}
+
+/// Generators of tests that break common wrong or slow approaches.
+impl Random {
+    /// `n` distinct multiples of `prime` in random order, none larger than `max`. Inserting them
+    /// into a GCC `std::unordered_map<long long, _>` whose bucket count is `prime` puts every key
+    /// into the same bucket.
+    pub fn anti_unordered_map(&mut self, n: usize, prime: u64, max: u64) -> Vec<u64> {
+        self.sample_distinct(n, 1, (max / prime) as i64)
+            .into_iter()
+            .map(|k| k as u64 * prime)
+            .collect()
+    }
+
+    /// Two different strings of length `len` over `alphabet` with the same `polynomial_hash` for
+    /// the given base and modulus, found with the birthday paradox. Takes about `sqrt(modulus)`
+    /// time and memory, so it suits single moduli around 10^9. There must be far more than
+    /// `sqrt(modulus)` possible strings, or this never returns.
+    pub fn anti_hash_pair(
+        &mut self,
+        len: usize,
+        alphabet: &str,
+        base: u64,
+        modulus: u64,
+    ) -> (String, String) {
+        let mut seen = HashMap::new();
+        loop {
+            let s = self.string_over(len, alphabet);
+            let hash = polynomial_hash(&s, base, modulus);
+            match seen.get(&hash) {
+                Some(other) if *other != s => return (s, seen.remove(&hash).unwrap()),
+                Some(_) => {}
+                None => {
+                    seen.insert(hash, s);
+                }
+            }
+        }
+    }
+
+    /// A `rows` x `cols` grid graph that makes SPFA (queue-based Bellman-Ford) relax edges many
+    /// times over: edges along rows are cheap and edges across rows are expensive, so shortest
+    /// paths keep improving as the queue sweeps the grid. Returns the number of nodes and
+    /// shuffled, relabelled weighted edges; node 1 is not special.
+    pub fn anti_spfa(&mut self, rows: usize, cols: usize) -> (usize, Vec<(usize, usize, i64)>) {
+        let n = rows * cols;
+        let labels = self.permutation(n);
+        let node = |i: usize, j: usize| labels[i * cols + j];
+        let mut edges = Vec::new();
+        for i in 0..rows {
+            for j in 0..cols {
+                if j + 1 < cols {
+                    edges.push((node(i, j), node(i, j + 1), self.next_range(1, 10)));
+                }
+                if i + 1 < rows {
+                    edges.push((node(i, j), node(i + 1, j), self.next_range(1, 1_000_000)));
+                }
+            }
+        }
+        self.shuffle(&mut edges);
+        (n, edges)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use super::*;
+    use crate::problemspec::predicates::{all_distinct, is_connected, is_permutation};
+    use crate::testspec::string::LOWERCASE;
+
+    fn quicksort_comparisons(v: &mut [i64]) -> usize {
+        if v.len() <= 1 {
+            return 0;
+        }
+        let middle = (v.len() - 1) / 2;
+        v.swap(0, middle);
+        let mut store = 0;
+        for i in 1..v.len() {
+            if v[i] < v[0] {
+                store += 1;
+                v.swap(i, store);
+            }
+        }
+        v.swap(0, store);
+        let comparisons = v.len() - 1;
+        let (left, right) = v.split_at_mut(store);
+        comparisons + quicksort_comparisons(left) + quicksort_comparisons(&mut right[1..])
+    }
+
+    /// How many times queue-based Bellman-Ford (SPFA) from `source` improves a distance.
+    fn spfa_relaxations(n: usize, edges: &[(usize, usize, i64)], source: usize) -> usize {
+        let mut adjacency = vec![Vec::new(); n + 1];
+        for &(u, v, w) in edges {
+            adjacency[u].push((v, w));
+            adjacency[v].push((u, w));
+        }
+        let mut distance = vec![i64::MAX; n + 1];
+        let mut queued = vec![false; n + 1];
+        let mut queue = VecDeque::from([source]);
+        distance[source] = 0;
+        queued[source] = true;
+        let mut relaxations = 0;
+        while let Some(u) = queue.pop_front() {
+            queued[u] = false;
+            for &(v, w) in &adjacency[u] {
+                if distance[u] + w < distance[v] {
+                    distance[v] = distance[u] + w;
+                    relaxations += 1;
+                    if !queued[v] {
+                        queued[v] = true;
+                        queue.push_back(v);
+                    }
+                }
+            }
+        }
+        relaxations
+    }
+
+    #[test]
+    fn test_anti_quicksort() {
+        let n = 1000;
+        let mut v = anti_quicksort(n);
+        assert!(is_permutation(&v).is_ok());
+        assert_eq!(quicksort_comparisons(&mut v), n * (n - 1) / 2);
+        assert!(v.windows(2).all(|w| w[0] < w[1]));
+
+        let mut shuffled: Vec<i64> = Random::new(0)
+            .permutation(n)
+            .into_iter()
+            .map(|x| x as i64)
+            .collect();
+        assert!(quicksort_comparisons(&mut shuffled) < n * 20);
+    }
+
+    #[test]
+    fn test_anti_unordered_map() {
+        let keys = Random::new(0).anti_unordered_map(1000, GCC_7_BUCKET_PRIME, 1_000_000_000);
+        assert_eq!(keys.len(), 1000);
+        assert!(all_distinct(&keys).is_ok());
+        assert!(keys
+            .iter()
+            .all(|&k| k % GCC_7_BUCKET_PRIME == 0 && 0 < k && k <= 1_000_000_000));
+    }
+
+    #[test]
+    fn test_anti_hash_pair() {
+        let (s, t) = Random::new(0).anti_hash_pair(10, LOWERCASE, 31, 1_000_000_007);
+        assert_ne!(s, t);
+        assert_eq!(s.len(), 10);
+        assert_eq!(
+            polynomial_hash(&s, 31, 1_000_000_007),
+            polynomial_hash(&t, 31, 1_000_000_007)
+        );
+    }
+
+    #[test]
+    fn test_anti_overflow_hash_pair() {
+        let (s, t) = anti_overflow_hash_pair(11);
+        assert_ne!(s, t);
+        let hash = |s: &str, base: u64| {
+            s.bytes().fold(0u64, |hash, c| {
+                hash.wrapping_mul(base).wrapping_add(c as u64)
+            })
+        };
+        for &base in &[31, 131, 1_000_003, 0x9e37_79b9_7f4a_7c15] {
+            assert_eq!(hash(&s, base), hash(&t, base));
+        }
+    }
+
+    #[test]
+    fn test_anti_spfa() {
+        let (rows, cols) = (4, 5);
+        let (n, edges) = Random::new(0).anti_spfa(rows, cols);
+        assert_eq!(n, rows * cols);
+        assert_eq!(edges.len(), rows * (cols - 1) + (rows - 1) * cols);
+
+        // The cheap edges form one path per row, and the expensive ones join nodes of different
+        // rows.
+        let (cheap, expensive): (Vec<_>, Vec<_>) = edges.iter().partition(|&&(_, _, w)| w <= 10);
+        assert_eq!(cheap.len(), rows * (cols - 1));
+        let mut row: Vec<usize> = (0..=n).collect();
+        fn find(row: &mut [usize], u: usize) -> usize {
+            if row[u] != u {
+                row[u] = find(row, row[u]);
+            }
+            row[u]
+        }
+        let mut degree = vec![0; n + 1];
+        for &&(u, v, _) in &cheap {
+            degree[u] += 1;
+            degree[v] += 1;
+            let (ru, rv) = (find(&mut row, u), find(&mut row, v));
+            assert_ne!(ru, rv, "cheap edges must not form a cycle");
+            row[ru] = rv;
+        }
+        assert!(degree.iter().all(|&d| d <= 2));
+        let mut sizes = vec![0; n + 1];
+        for u in 1..=n {
+            sizes[find(&mut row, u)] += 1;
+        }
+        sizes.retain(|&size| size > 0);
+        assert_eq!(sizes, vec![cols; rows]);
+        assert!(expensive
+            .iter()
+            .all(|&&(u, v, _)| find(&mut row, u) != find(&mut row, v)));
+
+        let unweighted: Vec<(usize, usize)> = edges.iter().map(|&(u, v, _)| (u, v)).collect();
+        assert!(is_connected(n, &unweighted).is_ok());
+    }
+
+    #[test]
+    fn test_anti_spfa_slows_down_spfa() {
+        let (n, edges) = Random::new(0).anti_spfa(100, 10);
+        let m = edges.len();
+        assert!(spfa_relaxations(n, &edges, 1) >= 10 * m);
+
+        // A random graph of the same size barely relaxes each edge more than once.
+        let mut rnd = Random::new(0);
+        let random = rnd.graph_connected(n, m);
+        let random = rnd.with_weights(&random, 1, 1_000_000);
+        assert!(spfa_relaxations(n, &random, 1) < 2 * m);
+    }
+}
@@ -0,0 +1,299 @@
+use std::{cmp::Ordering, collections::HashSet};
+
+use super::random::Random;
+
+/// Integer point `(x, y)`. Unzip a list of points into `xs` and `ys` to print them with
+/// `LINES!(V![xs], V![ys])`.
+pub type Point = (i64, i64);
+
+/// Cross product of `a - o` and `b - o`; positive when `o, a, b` turn counterclockwise.
+pub fn cross(o: Point, a: Point, b: Point) -> i128 {
+    (a.0 - o.0) as i128 * (b.1 - o.1) as i128 - (a.1 - o.1) as i128 * (b.0 - o.0) as i128
+}
+
+/// Geometry generators. Boxes are given by their corners `from` and `to`, both inclusive.
+impl Random {
+    /// `n` distinct random points in the box.
+    pub fn points(&mut self, n: usize, from: Point, to: Point) -> Vec<Point> {
+        let width = to.0 as i128 - from.0 as i128 + 1;
+        let height = to.1 as i128 - from.1 as i128 + 1;
+        let size = width.checked_mul(height).unwrap_or(i128::MAX);
+        assert!(
+            width > 0 && height > 0 && n as i128 <= size,
+            "cannot fit {} distinct points between {:?} and {:?}",
+            n,
+            from,
+            to
+        );
+        if size <= i64::MAX as i128 {
+            return self
+                .sample_distinct(n, 0, (size - 1) as i64)
+                .into_iter()
+                .map(|i| {
+                    let (i, width) = (i as i128, width);
+                    (
+                        (from.0 as i128 + i % width) as i64,
+                        (from.1 as i128 + i / width) as i64,
+                    )
+                })
+                .collect();
+        }
+        let mut seen = HashSet::new();
+        let mut result = Vec::with_capacity(n);
+        while result.len() < n {
+            let point = (self.next_range(from.0, to.0), self.next_range(from.1, to.1));
+            if seen.insert(point) {
+                result.push(point);
+            }
+        }
+        result
+    }
+
+    /// `n` distinct points in the box with no three on a line, taken from the curve
+    /// `y = x^2 mod p` for a random prime `p`. Needs a prime between `n` and the smaller side of
+    /// the box.
+    pub fn points_general_position(&mut self, n: usize, from: Point, to: Point) -> Vec<Point> {
+        let (width, height) = (
+            to.0 as i128 - from.0 as i128 + 1,
+            to.1 as i128 - from.1 as i128 + 1,
+        );
+        let side = width.min(height).min(i64::MAX as i128) as u64;
+        assert!(
+            n <= 2 || (n as u64) <= side,
+            "no room for {} points in general position between {:?} and {:?}",
+            n,
+            from,
+            to
+        );
+        if n <= 2 {
+            return self.points(n, from, to);
+        }
+        let p = self.prime(n as u64, side);
+        let swap = self.next::<bool>();
+        let dx = self.next_range(0, width - p as i128);
+        let dy = self.next_range(0, height - p as i128);
+        self.sample_distinct(n, 0, p as i64 - 1)
+            .into_iter()
+            .map(|x| {
+                let y = (x as u128 * x as u128 % p as u128) as i128;
+                let (x, y) = if swap { (y, x as i128) } else { (x as i128, y) };
+                (
+                    (from.0 as i128 + dx + x) as i64,
+                    (from.1 as i128 + dy + y) as i64,
+                )
+            })
+            .collect()
+    }
+
+    /// A random strictly convex polygon with `k` vertices in the box, in counterclockwise order,
+    /// built with Valtr's algorithm. Retries until no two edges are parallel, so `k` should be
+    /// well below the side of the box.
+    pub fn convex_polygon(&mut self, k: usize, from: Point, to: Point) -> Vec<Point> {
+        assert!(k >= 3, "a polygon needs at least 3 vertices");
+        loop {
+            let dxs = self.valtr_chains(k, from.0, to.0);
+            let mut dys = self.valtr_chains(k, from.1, to.1);
+            self.shuffle(&mut dys);
+            let mut vectors: Vec<Point> = dxs.into_iter().zip(dys).collect();
+            vectors.sort_by(|&a, &b| by_angle(a, b));
+            if vectors
+                .windows(2)
+                .any(|w| by_angle(w[0], w[1]) == Ordering::Equal)
+                || vectors.contains(&(0, 0))
+            {
+                continue;
+            }
+
+            let mut polygon = Vec::with_capacity(k);
+            let mut current = (0, 0);
+            for (dx, dy) in vectors {
+                polygon.push(current);
+                current = (current.0 + dx, current.1 + dy);
+            }
+            let min_x = polygon.iter().map(|p| p.0).min().unwrap();
+            let max_x = polygon.iter().map(|p| p.0).max().unwrap();
+            let min_y = polygon.iter().map(|p| p.1).min().unwrap();
+            let max_y = polygon.iter().map(|p| p.1).max().unwrap();
+            let x = self.next_range(from.0, to.0 - (max_x - min_x)) - min_x;
+            let y = self.next_range(from.1, to.1 - (max_y - min_y)) - min_y;
+            return polygon
+                .into_iter()
+                .map(|(px, py)| (px + x, py + y))
+                .collect();
+        }
+    }
+
+    /// A random simple polygon with `n` vertices in the box, in counterclockwise order: points
+    /// in general position sorted by angle around the lowest one.
+    pub fn simple_polygon(&mut self, n: usize, from: Point, to: Point) -> Vec<Point> {
+        assert!(n >= 3, "a polygon needs at least 3 vertices");
+        let mut points = self.points_general_position(n, from, to);
+        let lowest = (0..n).min_by_key(|&i| (points[i].1, points[i].0)).unwrap();
+        points.swap(0, lowest);
+        let pivot = points[0];
+        points[1..].sort_by(|&a, &b| 0.cmp(&cross(pivot, a, b)));
+        points
+    }
+
+    /// `n` distinct random integer points on the circle with the given center and radius.
+    /// Enumerates the circle in O(radius) time.
+    pub fn points_on_circle(&mut self, n: usize, center: Point, radius: i64) -> Vec<Point> {
+        let r2 = radius as i128 * radius as i128;
+        let mut candidates = Vec::new();
+        for x in -radius..=radius {
+            let rest = r2 - x as i128 * x as i128;
+            let y = (rest as f64).sqrt().round() as i128;
+            if let Some(y) = (y - 1..=y + 1).find(|y| y * y == rest) {
+                candidates.push((center.0 + x, center.1 + y as i64));
+                if y != 0 {
+                    candidates.push((center.0 + x, center.1 - y as i64));
+                }
+            }
+        }
+        assert!(
+            n <= candidates.len(),
+            "the circle of radius {} has only {} integer points",
+            radius,
+            candidates.len()
+        );
+        self.sample_distinct(n, 0, candidates.len() as i64 - 1)
+            .into_iter()
+            .map(|i| candidates[i as usize])
+            .collect()
+    }
+
+    /// `k` numbers summing to zero whose prefix sums stay within `to - from`, as in Valtr's
+    /// algorithm: sorted random coordinates split into two chains between the extremes.
+    fn valtr_chains(&mut self, k: usize, from: i64, to: i64) -> Vec<i64> {
+        let mut values = self.sample_distinct(k, from, to);
+        values.sort_unstable();
+        let (min, max) = (values[0], values[k - 1]);
+        let (mut last_up, mut last_down) = (min, min);
+        let mut result = Vec::with_capacity(k);
+        for &value in &values[1..k - 1] {
+            if self.next::<bool>() {
+                result.push(value - last_up);
+                last_up = value;
+            } else {
+                result.push(last_down - value);
+                last_down = value;
+            }
+        }
+        result.push(max - last_up);
+        result.push(last_down - max);
+        result
+    }
+}
+
+/// Orders vectors by their angle in `[0, 2 * pi)`.
+fn by_angle(a: Point, b: Point) -> Ordering {
+    let half = |(x, y): Point| y < 0 || (y == 0 && x < 0);
+    half(a)
+        .cmp(&half(b))
+        .then_with(|| 0.cmp(&cross((0, 0), a, b)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn in_box(points: &[Point], from: Point, to: Point) -> bool {
+        points
+            .iter()
+            .all(|&(x, y)| (from.0..=to.0).contains(&x) && (from.1..=to.1).contains(&y))
+    }
+
+    fn distinct(points: &[Point]) -> bool {
+        points.iter().collect::<HashSet<_>>().len() == points.len()
+    }
+
+    fn segments_cross(a: Point, b: Point, c: Point, d: Point) -> bool {
+        let (d1, d2) = (cross(a, b, c).signum(), cross(a, b, d).signum());
+        let (d3, d4) = (cross(c, d, a).signum(), cross(c, d, b).signum());
+        d1 * d2 < 0 && d3 * d4 < 0
+    }
+
+    #[test]
+    fn test_points() {
+        let mut rnd = Random::new(0);
+        let points = rnd.points(100, (-5, 0), (4, 9));
+        assert_eq!(points.len(), 100);
+        assert!(distinct(&points));
+        assert!(in_box(&points, (-5, 0), (4, 9)));
+
+        let (from, to) = ((i64::MIN, i64::MIN), (i64::MAX, i64::MAX));
+        let points = rnd.points(100, from, to);
+        assert!(distinct(&points));
+
+        let (xs, ys): (Vec<i64>, Vec<i64>) = points.into_iter().unzip();
+        assert_eq!((xs.len(), ys.len()), (100, 100));
+    }
+
+    #[test]
+    fn test_points_general_position() {
+        let mut rnd = Random::new(0);
+        let (from, to) = ((0, -1000), (1000, 1000));
+        let points = rnd.points_general_position(60, from, to);
+        assert_eq!(points.len(), 60);
+        assert!(distinct(&points));
+        assert!(in_box(&points, from, to));
+        for i in 0..points.len() {
+            for j in i + 1..points.len() {
+                for k in j + 1..points.len() {
+                    assert_ne!(cross(points[i], points[j], points[k]), 0);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_convex_polygon() {
+        let mut rnd = Random::new(0);
+        let (from, to) = ((-1_000_000, -1_000_000), (1_000_000, 1_000_000));
+        for &k in &[3, 10, 1000] {
+            let polygon = rnd.convex_polygon(k, from, to);
+            assert_eq!(polygon.len(), k);
+            assert!(in_box(&polygon, from, to));
+            assert!(
+                (0..k).all(|i| cross(polygon[i], polygon[(i + 1) % k], polygon[(i + 2) % k]) > 0)
+            );
+        }
+    }
+
+    #[test]
+    fn test_simple_polygon() {
+        let mut rnd = Random::new(0);
+        let (from, to) = ((0, 0), (100, 100));
+        let n = 40;
+        let polygon = rnd.simple_polygon(n, from, to);
+        assert_eq!(polygon.len(), n);
+        assert!(in_box(&polygon, from, to));
+        let edge = |i: usize| (polygon[i], polygon[(i + 1) % n]);
+        for i in 0..n {
+            for j in i + 2..n {
+                let ((a, b), (c, d)) = (edge(i), edge(j));
+                assert!(!segments_cross(a, b, c, d));
+            }
+        }
+        let area: i128 = (0..n)
+            .map(|i| cross((0, 0), polygon[i], polygon[(i + 1) % n]))
+            .sum();
+        assert!(area > 0);
+    }
+
+    #[test]
+    fn test_points_on_circle() {
+        let mut rnd = Random::new(0);
+        let points = rnd.points_on_circle(12, (3, -4), 5);
+        assert!(distinct(&points));
+        assert!(points
+            .iter()
+            .all(|&(x, y)| (x - 3).pow(2) + (y + 4).pow(2) == 25));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_points_on_circle_rejects_too_many_points() {
+        Random::new(0).points_on_circle(13, (0, 0), 5);
+    }
+}
@@ -0,0 +1,297 @@
+use std::collections::HashSet;
+
+use super::{
+    random::{Random, SampleRange},
+    tree::Edges,
+};
+
+/// Graph generators. Nodes are numbered from 1 and, unless stated otherwise, graphs are simple:
+/// no self loops and no multiple edges.
+impl Random {
+    /// Random simple undirected graph with `n` nodes and `m` edges.
+    pub fn graph(&mut self, n: usize, m: usize) -> Edges {
+        assert!(
+            m <= n * n.saturating_sub(1) / 2,
+            "too many edges for n = {}",
+            n
+        );
+        let edges = self.distinct_pairs(
+            m,
+            &HashSet::new(),
+            n * n.saturating_sub(1) / 2,
+            |random| unordered_pair(random, n),
+            || all_pairs(n),
+        );
+        self.shuffle_edges(&edges)
+    }
+
+    /// Random simple connected undirected graph with `n` nodes and `m` edges: a random spanning
+    /// tree plus `m - (n - 1)` random extra edges.
+    pub fn graph_connected(&mut self, n: usize, m: usize) -> Edges {
+        assert!(n >= 1, "a connected graph needs at least one node");
+        assert!(
+            m + 1 >= n,
+            "a connected graph with {} nodes needs {} edges",
+            n,
+            n - 1
+        );
+        assert!(m <= n * (n - 1) / 2, "too many edges for n = {}", n);
+        let tree = self.tree(n);
+        let tree = self.relabel(n, &tree);
+        let existing: HashSet<(usize, usize)> =
+            tree.iter().map(|&(u, v)| (u.min(v), u.max(v))).collect();
+        let mut edges = self.distinct_pairs(
+            m + 1 - n,
+            &existing,
+            n * (n - 1) / 2 - (n - 1),
+            |random| unordered_pair(random, n),
+            || {
+                all_pairs(n)
+                    .into_iter()
+                    .filter(|edge| !existing.contains(edge))
+                    .collect()
+            },
+        );
+        edges.extend(tree);
+        self.shuffle_edges(&edges)
+    }
+
+    /// Random directed acyclic graph with `n` nodes and `m` edges, in random topological order.
+    pub fn dag(&mut self, n: usize, m: usize) -> Edges {
+        assert!(
+            m <= n * n.saturating_sub(1) / 2,
+            "too many edges for n = {}",
+            n
+        );
+        let edges = self.distinct_pairs(
+            m,
+            &HashSet::new(),
+            n * n.saturating_sub(1) / 2,
+            |random| unordered_pair(random, n),
+            || all_pairs(n),
+        );
+        let mut edges = self.relabel(n, &edges);
+        self.shuffle(&mut edges);
+        edges
+    }
+
+    /// Random simple bipartite graph with `m` edges between the nodes `1..=left` and the nodes
+    /// `left + 1..=left + right`.
+    pub fn bipartite(&mut self, left: usize, right: usize, m: usize) -> Edges {
+        assert!(
+            m <= left * right,
+            "too many edges for {} x {} nodes",
+            left,
+            right
+        );
+        let mut edges = self.distinct_pairs(
+            m,
+            &HashSet::new(),
+            left * right,
+            |random| {
+                Some((
+                    random.next_range(1, left),
+                    left + random.next_range(1, right),
+                ))
+            },
+            || {
+                (1..=left)
+                    .flat_map(|u| (1..=right).map(move |v| (u, left + v)))
+                    .collect()
+            },
+        );
+        self.shuffle(&mut edges);
+        edges
+    }
+
+    /// Attaches a random weight `next_range(from, to)` to every edge.
+    pub fn with_weights<W>(
+        &mut self,
+        edges: &[(usize, usize)],
+        from: W,
+        to: W,
+    ) -> Vec<(usize, usize, W)>
+    where
+        W: SampleRange + PartialOrd + Copy,
+    {
+        edges
+            .iter()
+            .map(|&(u, v)| (u, v, self.next_range(from, to)))
+            .collect()
+    }
+
+    /// Samples `m` distinct pairs out of `total` candidates, none of them in `existing`.
+    /// Sparse requests draw from `sample` (which may reject a draw by returning `None`), dense
+    /// ones take a random subset of the candidates listed by `all`.
+    fn distinct_pairs<S, A>(
+        &mut self,
+        m: usize,
+        existing: &HashSet<(usize, usize)>,
+        total: usize,
+        mut sample: S,
+        all: A,
+    ) -> Edges
+    where
+        S: FnMut(&mut Random) -> Option<(usize, usize)>,
+        A: FnOnce() -> Edges,
+    {
+        if 2 * m > total {
+            let mut candidates = all();
+            for i in 0..m {
+                let j = self.next_range(i, candidates.len() - 1);
+                candidates.swap(i, j);
+            }
+            candidates.truncate(m);
+            return candidates;
+        }
+
+        let mut seen = HashSet::new();
+        let mut edges = Vec::with_capacity(m);
+        while edges.len() < m {
+            if let Some(edge) = sample(self) {
+                if !existing.contains(&edge) && seen.insert(edge) {
+                    edges.push(edge);
+                }
+            }
+        }
+        edges
+    }
+}
+
+/// The `height` x `width` grid as a graph, where cell `(i, j)` (from 0) is node
+/// `i * width + j + 1` and is connected to its horizontal and vertical neighbours.
+pub fn grid_graph(height: usize, width: usize) -> Edges {
+    let node = |i: usize, j: usize| i * width + j + 1;
+    let mut edges = Vec::new();
+    for i in 0..height {
+        for j in 0..width {
+            if j + 1 < width {
+                edges.push((node(i, j), node(i, j + 1)));
+            }
+            if i + 1 < height {
+                edges.push((node(i, j), node(i + 1, j)));
+            }
+        }
+    }
+    edges
+}
+
+/// A random pair `(u, v)` with `1 <= u < v <= n`, or `None` when both draws hit the same node.
+fn unordered_pair(random: &mut Random, n: usize) -> Option<(usize, usize)> {
+    let (u, v) = (random.next_range(1, n), random.next_range(1, n));
+    if u != v {
+        Some((u.min(v), u.max(v)))
+    } else {
+        None
+    }
+}
+
+fn all_pairs(n: usize) -> Edges {
+    (1..=n)
+        .flat_map(|u| (u + 1..=n).map(move |v| (u, v)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::problemspec::predicates::{is_connected, no_multi_edges, no_self_loops};
+
+    fn is_simple(edges: &[(usize, usize)]) -> bool {
+        no_self_loops(edges).is_ok() && no_multi_edges(edges).is_ok()
+    }
+
+    #[test]
+    fn test_graph() {
+        let mut rnd = Random::new(0);
+        for &(n, m) in &[(1, 0), (5, 3), (5, 10), (50, 100), (50, 1000)] {
+            let edges = rnd.graph(n, m);
+            assert_eq!(edges.len(), m);
+            assert!(is_simple(&edges));
+            assert!(edges
+                .iter()
+                .all(|&(u, v)| (1..=n).contains(&u) && (1..=n).contains(&v)));
+        }
+    }
+
+    #[test]
+    fn test_graph_connected() {
+        let mut rnd = Random::new(0);
+        for &(n, m) in &[(1, 0), (2, 1), (5, 4), (5, 10), (50, 60), (50, 1200)] {
+            let edges = rnd.graph_connected(n, m);
+            assert_eq!(edges.len(), m);
+            assert!(is_simple(&edges));
+            assert!(is_connected(n, &edges).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_dag_is_acyclic() {
+        let mut rnd = Random::new(0);
+        let n = 30;
+        let edges = rnd.dag(n, 200);
+        assert_eq!(edges.len(), 200);
+        assert!(is_simple(&edges));
+
+        let mut indegree = vec![0; n + 1];
+        let mut adjacency = vec![Vec::new(); n + 1];
+        for &(u, v) in &edges {
+            adjacency[u].push(v);
+            indegree[v] += 1;
+        }
+        let mut queue: Vec<usize> = (1..=n).filter(|&u| indegree[u] == 0).collect();
+        let mut visited = 0;
+        while let Some(u) = queue.pop() {
+            visited += 1;
+            for &v in &adjacency[u] {
+                indegree[v] -= 1;
+                if indegree[v] == 0 {
+                    queue.push(v);
+                }
+            }
+        }
+        assert_eq!(visited, n);
+    }
+
+    #[test]
+    fn test_bipartite() {
+        let mut rnd = Random::new(0);
+        let edges = rnd.bipartite(3, 4, 12);
+        assert_eq!(edges.len(), 12);
+        assert!(is_simple(&edges));
+        assert!(edges.iter().all(|&(u, v)| u <= 3 && (4..=7).contains(&v)));
+        assert_eq!(rnd.bipartite(100, 100, 50).len(), 50);
+    }
+
+    #[test]
+    fn test_grid_graph() {
+        assert_eq!(grid_graph(2, 2), vec![(1, 2), (1, 3), (2, 4), (3, 4)]);
+        let edges = grid_graph(3, 5);
+        assert_eq!(edges.len(), 3 * 4 + 2 * 5);
+        assert!(is_connected(15, &edges).is_ok());
+    }
+
+    #[test]
+    fn test_with_weights() {
+        let mut rnd = Random::new(0);
+        let edges = rnd.graph_connected(10, 20);
+        let weighted = rnd.with_weights(&edges, 1, 5);
+        assert_eq!(weighted.len(), 20);
+        assert!(weighted
+            .iter()
+            .zip(edges.iter())
+            .all(|(&(u, v, w), &(x, y))| u == x && v == y && (1..=5).contains(&w)));
+    }
+
+    #[test]
+    fn test_graph_is_deterministic() {
+        assert_eq!(
+            Random::new(1).graph_connected(100, 300),
+            Random::new(1).graph_connected(100, 300)
+        );
+        assert_ne!(
+            Random::new(1).graph_connected(100, 300),
+            Random::new(2).graph_connected(100, 300)
+        );
+    }
+}
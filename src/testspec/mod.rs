@@ -1,2 +1,9 @@
+pub mod adversarial;
+pub mod geometry;
+pub mod graph;
+pub mod number;
+pub mod partition;
 pub mod random;
 pub mod spec;
+pub mod string;
+pub mod tree;
@@ -0,0 +1,261 @@
+use std::collections::HashSet;
+
+use super::random::Random;
+
+/// Miller-Rabin bases that give a deterministic answer for every `u64`.
+const WITNESSES: [u64; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+
+/// Deterministic primality test for any `u64`.
+pub fn is_prime(n: u64) -> bool {
+    if n < 2 {
+        return false;
+    }
+    for &p in &WITNESSES {
+        if n % p == 0 {
+            return n == p;
+        }
+    }
+    let mut d = n - 1;
+    let mut s = 0;
+    while d & 1 == 0 {
+        d /= 2;
+        s += 1;
+    }
+    WITNESSES.iter().all(|&a| {
+        let mut x = pow_mod(a, d, n);
+        if x == 1 || x == n - 1 {
+            return true;
+        }
+        for _ in 1..s {
+            x = mul_mod(x, x, n);
+            if x == n - 1 {
+                return true;
+            }
+        }
+        false
+    })
+}
+
+fn mul_mod(a: u64, b: u64, m: u64) -> u64 {
+    (a as u128 * b as u128 % m as u128) as u64
+}
+
+fn pow_mod(mut base: u64, mut exponent: u64, m: u64) -> u64 {
+    let mut result = 1;
+    base %= m;
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = mul_mod(result, base, m);
+        }
+        base = mul_mod(base, base, m);
+        exponent >>= 1;
+    }
+    result
+}
+
+/// The smallest number up to `max` with the most divisors (a highly composite number), the
+/// usual worst case for divisor enumeration.
+pub fn most_divisors(max: u64) -> u64 {
+    // Exponents of the best number never increase along the primes, so a small search over
+    // non-increasing exponent sequences finds it.
+    fn search(
+        primes: &[u64],
+        max: u64,
+        value: u64,
+        divisors: u64,
+        limit: u32,
+        best: &mut (u64, u64),
+    ) {
+        if divisors > best.1 || (divisors == best.1 && value < best.0) {
+            *best = (value, divisors);
+        }
+        if let Some((&p, primes)) = primes.split_first() {
+            let mut value = value;
+            for exponent in 1..=limit {
+                value = match value.checked_mul(p) {
+                    Some(value) if value <= max => value,
+                    _ => break,
+                };
+                search(
+                    primes,
+                    max,
+                    value,
+                    divisors * (exponent as u64 + 1),
+                    exponent,
+                    best,
+                );
+            }
+        }
+    }
+
+    assert!(max >= 1, "max must be positive");
+    let primes: Vec<u64> = (2..).filter(|&x| is_prime(x)).take(15).collect();
+    let mut best = (1, 1);
+    search(&primes, max, 1, 1, u64::BITS, &mut best);
+    best.0
+}
+
+/// Number theory generators.
+impl Random {
+    /// A random prime in `[from, to]`: the first prime at or after a random starting point,
+    /// wrapping around to `from`. Panics if the range has no prime.
+    pub fn prime(&mut self, from: u64, to: u64) -> u64 {
+        self.prime_not_in(from, to, &HashSet::new())
+            .unwrap_or_else(|| panic!("no prime in [{}, {}]", from, to))
+    }
+
+    /// Like `prime`, skipping the primes in `used`. `None` once every prime in the range is used.
+    fn prime_not_in(&mut self, from: u64, to: u64, used: &HashSet<u64>) -> Option<u64> {
+        let start = self.next_range(from, to);
+        (start..=to)
+            .chain(from..start)
+            .find(|x| !used.contains(x) && is_prime(*x))
+    }
+
+    /// Product of two distinct random primes in `[from, to]`.
+    pub fn semiprime(&mut self, from: u64, to: u64) -> u64 {
+        self.with_factorization(&[1, 1], from, to)
+    }
+
+    /// A random Carmichael number up to `max`, of the form `(6k + 1)(12k + 1)(18k + 1)` with all
+    /// three factors prime. These fool Fermat primality tests for every base coprime to them.
+    pub fn carmichael(&mut self, max: u64) -> u64 {
+        let candidates: Vec<u64> = (1..)
+            .map(|k: u128| (6 * k + 1, 12 * k + 1, 18 * k + 1))
+            .take_while(|(a, b, c)| a * b * c <= max as u128)
+            .filter(|&(a, b, c)| is_prime(a as u64) && is_prime(b as u64) && is_prime(c as u64))
+            .map(|(a, b, c)| (a * b * c) as u64)
+            .collect();
+        *self.choose(&candidates).unwrap_or_else(|| {
+            panic!(
+                "no Carmichael number of the form (6k+1)(12k+1)(18k+1) up to {}",
+                max
+            )
+        })
+    }
+
+    /// The product of distinct random primes from `[from, to]`, the `i`-th one raised to
+    /// `exponents[i]`. For example `&[2, 1]` gives `p^2 * q`. Panics if the range has fewer
+    /// primes than `exponents`, or if the result does not fit in a `u64`.
+    pub fn with_factorization(&mut self, exponents: &[u32], from: u64, to: u64) -> u64 {
+        let mut primes = HashSet::new();
+        let mut result: u64 = 1;
+        for &exponent in exponents {
+            let p = self.prime_not_in(from, to, &primes).unwrap_or_else(|| {
+                panic!(
+                    "factorization {:?} needs {} distinct primes, [{}, {}] has {}",
+                    exponents,
+                    exponents.len(),
+                    from,
+                    to,
+                    primes.len()
+                )
+            });
+            primes.insert(p);
+            result = p
+                .checked_pow(exponent)
+                .and_then(|power| result.checked_mul(power))
+                .unwrap_or_else(|| panic!("factorization {:?} overflows u64", exponents));
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn factorize(mut n: u64) -> Vec<(u64, u32)> {
+        let mut factors = Vec::new();
+        let mut p = 2;
+        while p * p <= n {
+            let mut exponent = 0;
+            while n % p == 0 {
+                n /= p;
+                exponent += 1;
+            }
+            if exponent > 0 {
+                factors.push((p, exponent));
+            }
+            p += 1;
+        }
+        if n > 1 {
+            factors.push((n, 1));
+        }
+        factors
+    }
+
+    #[test]
+    fn test_is_prime() {
+        let small: Vec<u64> = (0..30).filter(|&x| is_prime(x)).collect();
+        assert_eq!(small, vec![2, 3, 5, 7, 11, 13, 17, 19, 23, 29]);
+        assert!(is_prime(1_000_000_007));
+        assert!(is_prime(18_446_744_073_709_551_557));
+        assert!(!is_prime(3_215_031_751));
+        assert!(!is_prime(561));
+        assert!(!is_prime(1_000_000_007 * 998_244_353));
+    }
+
+    #[test]
+    fn test_prime() {
+        let mut rnd = Random::new(0);
+        for _ in 0..100 {
+            let p = rnd.prime(1_000_000_000_000, 1_000_000_001_000);
+            assert!((1_000_000_000_000..=1_000_000_001_000).contains(&p));
+            assert_eq!(factorize(p), vec![(p, 1)]);
+        }
+        assert_eq!(rnd.prime(24, 29), 29);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_prime_panics_without_primes() {
+        Random::new(0).prime(24, 28);
+    }
+
+    #[test]
+    fn test_most_divisors() {
+        assert_eq!(most_divisors(1), 1);
+        assert_eq!(most_divisors(100), 60);
+        assert_eq!(most_divisors(1_000_000_000), 735_134_400);
+        assert_eq!(
+            most_divisors(1_000_000_000_000_000_000),
+            897_612_484_786_617_600
+        );
+    }
+
+    #[test]
+    fn test_semiprime() {
+        let n = Random::new(0).semiprime(1000, 2000);
+        let factors = factorize(n);
+        assert_eq!(factors.len(), 2);
+        assert!(factors
+            .iter()
+            .all(|&(p, e)| (1000..=2000).contains(&p) && e == 1));
+    }
+
+    #[test]
+    #[should_panic(expected = "needs 2 distinct primes, [24, 29] has 1")]
+    fn test_semiprime_panics_without_two_primes() {
+        Random::new(0).semiprime(24, 29);
+    }
+
+    #[test]
+    fn test_carmichael() {
+        let mut rnd = Random::new(0);
+        assert_eq!(rnd.carmichael(2000), 1729);
+        for _ in 0..10 {
+            let n = rnd.carmichael(1_000_000_000_000);
+            assert!(!is_prime(n));
+            assert_eq!(pow_mod(2, n - 1, n), 1);
+        }
+    }
+
+    #[test]
+    fn test_with_factorization() {
+        let n = Random::new(0).with_factorization(&[3, 2, 1], 2, 50);
+        let mut exponents: Vec<u32> = factorize(n).into_iter().map(|(_, e)| e).collect();
+        exponents.sort_unstable();
+        assert_eq!(exponents, vec![1, 2, 3]);
+    }
+}
@@ -0,0 +1,155 @@
+use super::random::Random;
+
+/// Generators for numbers with a fixed sum.
+impl Random {
+    /// `parts` positive numbers summing to `total`, uniformly among all such sequences.
+    pub fn composition(&mut self, total: usize, parts: usize) -> Vec<usize> {
+        self.composition_bounded(total, parts, 1, total)
+    }
+
+    /// `parts` numbers in `[min, max]` summing to `total`.
+    pub fn composition_bounded(
+        &mut self,
+        total: usize,
+        parts: usize,
+        min: usize,
+        max: usize,
+    ) -> Vec<usize> {
+        self.composition_with_bounds(total, &vec![(min, max); parts])
+    }
+
+    /// Numbers summing to `total` where the `i`-th one is within `bounds[i]`, both inclusive.
+    /// Without tight bounds the result is uniform; parts that would exceed their bound are
+    /// clamped and the excess is spread over the parts with room left.
+    pub fn composition_with_bounds(
+        &mut self,
+        total: usize,
+        bounds: &[(usize, usize)],
+    ) -> Vec<usize> {
+        let min_total: usize = bounds.iter().map(|&(min, _)| min).sum();
+        let max_total: usize = bounds.iter().map(|&(_, max)| max).sum();
+        assert!(
+            bounds.iter().all(|&(min, max)| min <= max),
+            "invalid bounds {:?}",
+            bounds
+        );
+        assert!(
+            min_total <= total && total <= max_total,
+            "cannot split {} into parts within {:?}",
+            total,
+            bounds
+        );
+        if bounds.is_empty() {
+            return Vec::new();
+        }
+
+        // Stars and bars: `parts - 1` distinct bars among `rest + parts - 1` slots.
+        let rest = total - min_total;
+        let parts = bounds.len();
+        let mut bars = self.sample_distinct(parts - 1, 1, (rest + parts - 1) as i64);
+        bars.push(0);
+        bars.push((rest + parts) as i64);
+        bars.sort_unstable();
+        let mut result: Vec<usize> = bars
+            .windows(2)
+            .map(|w| (w[1] - w[0] - 1) as usize)
+            .collect();
+
+        let mut excess = 0;
+        for (x, &(min, max)) in result.iter_mut().zip(bounds) {
+            excess += x.saturating_sub(max - min);
+            *x = min + (*x).min(max - min);
+        }
+        while excess > 0 {
+            let mut open: Vec<usize> = (0..parts).filter(|&i| result[i] < bounds[i].1).collect();
+            self.shuffle(&mut open);
+            for i in open {
+                let add = self.next_range(1, (bounds[i].1 - result[i]).min(excess));
+                result[i] += add;
+                excess -= add;
+                if excess == 0 {
+                    break;
+                }
+            }
+        }
+        result
+    }
+
+    /// A partition of `total` into `parts` numbers in `[min, max]`, in non-increasing order.
+    pub fn partition(&mut self, total: usize, parts: usize, min: usize, max: usize) -> Vec<usize> {
+        let mut result = self.composition_bounded(total, parts, min, max);
+        result.sort_unstable_by(|a, b| b.cmp(a));
+        result
+    }
+
+    /// Sizes for `cases` test cases in one file, each at least `min`, adding up to exactly
+    /// `total`. Use it to hit limits such as "the sum of N over all test cases is at most
+    /// 2 * 10^5" with `MultipleTestcaseConfig`.
+    pub fn split_budget(&mut self, total: usize, cases: usize, min: usize) -> Vec<usize> {
+        self.composition_bounded(total, cases, min, total)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_composition() {
+        let mut rnd = Random::new(0);
+        for &(total, parts) in &[(1, 1), (10, 3), (10, 10), (1_000_000_000, 5)] {
+            let v = rnd.composition(total, parts);
+            assert_eq!(v.len(), parts);
+            assert_eq!(v.iter().sum::<usize>(), total);
+            assert!(v.iter().all(|&x| x >= 1));
+        }
+        assert!(rnd.composition(0, 0).is_empty());
+    }
+
+    #[test]
+    fn test_composition_bounded() {
+        let mut rnd = Random::new(0);
+        for _ in 0..100 {
+            let v = rnd.composition_bounded(100, 10, 5, 12);
+            assert_eq!(v.iter().sum::<usize>(), 100);
+            assert!(v.iter().all(|x| (5..=12).contains(x)));
+        }
+        assert_eq!(rnd.composition_bounded(12, 3, 2, 4), vec![4, 4, 4]);
+    }
+
+    #[test]
+    fn test_composition_with_bounds() {
+        let mut rnd = Random::new(0);
+        let bounds = [(0, 0), (1, 100), (50, 60), (0, 5)];
+        for _ in 0..100 {
+            let v = rnd.composition_with_bounds(100, &bounds);
+            assert_eq!(v.iter().sum::<usize>(), 100);
+            assert!(v
+                .iter()
+                .zip(bounds.iter())
+                .all(|(x, &(min, max))| (min..=max).contains(x)));
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_composition_with_bounds_rejects_impossible_total() {
+        Random::new(0).composition_bounded(100, 3, 1, 10);
+    }
+
+    #[test]
+    fn test_partition() {
+        let v = Random::new(0).partition(50, 6, 2, 20);
+        assert_eq!(v.iter().sum::<usize>(), 50);
+        assert!(v.windows(2).all(|w| w[0] >= w[1]));
+        assert!(v.iter().all(|x| (2..=20).contains(x)));
+    }
+
+    #[test]
+    fn test_split_budget() {
+        let v = Random::new(0).split_budget(200_000, 7, 1000);
+        assert_eq!(v.len(), 7);
+        assert_eq!(v.iter().sum::<usize>(), 200_000);
+        assert!(v.iter().all(|&x| x >= 1000));
+    }
+}
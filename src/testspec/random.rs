@@ -1,55 +1,341 @@
-use rand::{
-    distributions::{uniform::SampleUniform, Alphanumeric, Standard},
-    prelude::Distribution,
-    Rng, SeedableRng,
-};
+use std::collections::HashSet;
 
+use super::string::{DIGITS, LOWERCASE, UPPERCASE};
+
+const GOLDEN_GAMMA: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/// Deterministic random generator. The numbers it produces are part of the test files it
+/// generates, so they are fixed for good: the generator is xoshiro256** seeded with four outputs
+/// of SplitMix64, and every value is derived from its 64-bit outputs as documented on `Sample`
+/// and `SampleRange`. None of this may change, or the same seed would give different tests.
 pub struct Random {
-    rnd: rand::rngs::StdRng,
+    state: [u64; 4],
+    seed: u64,
+}
+
+/// The SplitMix64 output function.
+fn mix(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Seed of the stream `stream` derived from `seed`, mixed with the SplitMix64 finalizer so that
+/// nearby seeds and streams give unrelated results.
+pub fn derive_seed(seed: u64, stream: u64) -> u64 {
+    mix(seed.wrapping_add(stream.wrapping_add(1).wrapping_mul(GOLDEN_GAMMA)))
+}
+
+/// Types `Random::next` can produce.
+pub trait Sample: Sized {
+    fn sample(random: &mut Random) -> Self;
+}
+
+/// Integers are the low bits of one output (two outputs, high first, for 128 bits).
+macro_rules! impl_sample_int {
+    ($($t:ty),*) => {
+        $(
+            impl Sample for $t {
+                fn sample(random: &mut Random) -> Self {
+                    random.next_u64() as $t
+                }
+            }
+        )*
+    };
+}
+
+impl_sample_int!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize);
+
+impl Sample for u128 {
+    fn sample(random: &mut Random) -> Self {
+        let high = random.next_u64() as u128;
+        (high << 64) | random.next_u64() as u128
+    }
+}
+
+impl Sample for i128 {
+    fn sample(random: &mut Random) -> Self {
+        u128::sample(random) as i128
+    }
+}
+
+/// The highest bit of one output.
+impl Sample for bool {
+    fn sample(random: &mut Random) -> Self {
+        random.next_u64() >> 63 == 1
+    }
+}
+
+/// The highest 53 bits of one output, scaled to `[0, 1)`.
+impl Sample for f64 {
+    fn sample(random: &mut Random) -> Self {
+        (random.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// The highest 24 bits of one output, scaled to `[0, 1)`.
+impl Sample for f32 {
+    fn sample(random: &mut Random) -> Self {
+        (random.next_u64() >> 40) as f32 / (1u32 << 24) as f32
+    }
+}
+
+/// A uniformly random Unicode scalar value, skipping the surrogates.
+impl Sample for char {
+    fn sample(random: &mut Random) -> Self {
+        const SURROGATES: u32 = 0xe000 - 0xd800;
+        let value = random.next_range(0, char::MAX as u32 - SURROGATES);
+        let value = if value >= 0xd800 {
+            value + SURROGATES
+        } else {
+            value
+        };
+        char::from_u32(value).unwrap()
+    }
+}
+
+/// Types `Random::next_range` can produce.
+pub trait SampleRange: Sized {
+    /// A uniformly random value in `[from, to]` for integers, and in `[from, to)` for floats.
+    fn sample_range(random: &mut Random, from: Self, to: Self) -> Self;
+}
+
+/// Integers take the offset from `from` as `x % size` for the first 128-bit sample `x` (see
+/// `u128`) that is at least `2^128 mod size`, which makes every offset equally likely.
+macro_rules! impl_sample_range_int {
+    ($($t:ty => $u:ty),*) => {
+        $(
+            impl SampleRange for $t {
+                fn sample_range(random: &mut Random, from: Self, to: Self) -> Self {
+                    let size = (to as $u).wrapping_sub(from as $u) as u128 + 1;
+                    (from as $u).wrapping_add(random.below(size) as $u) as $t
+                }
+            }
+        )*
+    };
+}
+
+impl_sample_range_int!(
+    u8 => u8, u16 => u16, u32 => u32, u64 => u64, usize => usize,
+    i8 => u8, i16 => u16, i32 => u32, i64 => u64, isize => usize
+);
+
+impl SampleRange for u128 {
+    fn sample_range(random: &mut Random, from: Self, to: Self) -> Self {
+        match to.wrapping_sub(from).checked_add(1) {
+            Some(size) => from.wrapping_add(random.below(size)),
+            None => random.next(),
+        }
+    }
+}
+
+impl SampleRange for i128 {
+    fn sample_range(random: &mut Random, from: Self, to: Self) -> Self {
+        u128::sample_range(random, from as u128, to as u128) as i128
+    }
+}
+
+/// Floats are `from + (to - from) * x` for an `x` sampled in `[0, 1)`, so `to` itself is only
+/// reached through rounding.
+impl SampleRange for f64 {
+    fn sample_range(random: &mut Random, from: Self, to: Self) -> Self {
+        from + (to - from) * random.next::<f64>()
+    }
+}
+
+impl SampleRange for f32 {
+    fn sample_range(random: &mut Random, from: Self, to: Self) -> Self {
+        from + (to - from) * random.next::<f32>()
+    }
 }
 
 impl Random {
     pub fn new(seed: u64) -> Self {
+        let mut splitmix = seed;
+        let mut next = || {
+            splitmix = splitmix.wrapping_add(GOLDEN_GAMMA);
+            mix(splitmix)
+        };
         Random {
-            rnd: rand::rngs::StdRng::seed_from_u64(seed),
+            state: [next(), next(), next(), next()],
+            seed,
         }
     }
 
-    pub fn next<T>(&mut self) -> T
-    where
-        Standard: Distribution<T>,
-    {
-        self.rnd.gen()
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// An independent generator for stream `stream`, e.g. the index of a test case. It only
+    /// depends on this generator's seed, not on how much of it was used, so changing how one
+    /// test case is generated leaves the others unchanged.
+    pub fn derive(&self, stream: u64) -> Random {
+        Random::new(derive_seed(self.seed, stream))
+    }
+
+    /// The next output of xoshiro256**.
+    pub fn next_u64(&mut self) -> u64 {
+        let s = &mut self.state;
+        let result = s[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = s[1] << 17;
+        s[2] ^= s[0];
+        s[3] ^= s[1];
+        s[1] ^= s[2];
+        s[0] ^= s[3];
+        s[2] ^= t;
+        s[3] = s[3].rotate_left(45);
+        result
+    }
+
+    /// A uniformly random number in `[0, size)`, for `size > 0`.
+    fn below(&mut self, size: u128) -> u128 {
+        let threshold = size.wrapping_neg() % size;
+        loop {
+            let x: u128 = self.next();
+            if x >= threshold {
+                return x % size;
+            }
+        }
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn next<T: Sample>(&mut self) -> T {
+        T::sample(self)
     }
 
     pub fn next_range<T>(&mut self, from: T, to: T) -> T
     where
-        T: SampleUniform + PartialOrd,
+        T: SampleRange + PartialOrd,
     {
-        self.rnd.gen_range(from..=to)
+        assert!(from <= to, "cannot sample from an empty range");
+        T::sample_range(self, from, to)
     }
 
+    /// Random string of ASCII letters and digits.
     pub fn next_string(&mut self, len: usize) -> String {
-        (&mut self.rnd)
-            .sample_iter(Alphanumeric)
-            .take(len)
-            .map(char::from)
-            .collect()
+        self.string_over(len, &format!("{}{}{}", UPPERCASE, LOWERCASE, DIGITS))
+    }
+
+    /// Fisher-Yates shuffle.
+    pub fn shuffle<T>(&mut self, v: &mut [T]) {
+        for i in (1..v.len()).rev() {
+            let j = self.next_range(0, i);
+            v.swap(i, j);
+        }
+    }
+
+    /// Random permutation of `1..=n`.
+    pub fn permutation(&mut self, n: usize) -> Vec<usize> {
+        let mut result: Vec<usize> = (1..=n).collect();
+        self.shuffle(&mut result);
+        result
+    }
+
+    /// A random element of `v`, or `None` if it is empty.
+    pub fn choose<'a, T>(&mut self, v: &'a [T]) -> Option<&'a T> {
+        if v.is_empty() {
+            None
+        } else {
+            Some(&v[self.next_range(0, v.len() - 1)])
+        }
+    }
+
+    /// `k` distinct numbers from `[from, to]` in random order. Takes O(k) time and memory, so
+    /// the range can be arbitrarily large.
+    pub fn sample_distinct(&mut self, k: usize, from: i64, to: i64) -> Vec<i64> {
+        let size = to as i128 - from as i128 + 1;
+        assert!(
+            k as i128 <= size.max(0),
+            "cannot sample {} distinct numbers from [{}, {}]",
+            k,
+            from,
+            to
+        );
+
+        // Floyd's algorithm: for each j in the last k positions of the range, pick a random
+        // number up to j, falling back to j itself when it was already picked.
+        let mut picked = HashSet::with_capacity(k);
+        let mut result = Vec::with_capacity(k);
+        for j in (size - k as i128)..size {
+            let offset = self.next_range(0, j);
+            let offset = if picked.insert(offset) {
+                offset
+            } else {
+                picked.insert(j);
+                j
+            };
+            result.push((from as i128 + offset) as i64);
+        }
+        self.shuffle(&mut result);
+        result
+    }
+
+    /// A random index of `weights`, picked with probability proportional to its weight.
+    pub fn weighted_choice(&mut self, weights: &[f64]) -> usize {
+        assert!(
+            weights.iter().all(|&weight| weight >= 0.0),
+            "weights must not be negative"
+        );
+        let total: f64 = weights.iter().sum();
+        assert!(total > 0.0, "at least one weight must be positive");
+
+        let mut target = self.next::<f64>() * total;
+        for (i, &weight) in weights.iter().enumerate() {
+            if target < weight {
+                return i;
+            }
+            target -= weight;
+        }
+        // Rounding may leave `target` just past the last weight.
+        weights.iter().rposition(|&weight| weight > 0.0).unwrap()
     }
 }
 
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
 mod tests {
     use super::*;
 
     #[test]
     fn test_next_to_be_deterministic() {
-        assert_eq!(Random::new(0).next::<i32>(), -852725889);
-        assert_eq!(Random::new(0).next::<u32>(), 3442241407);
-        assert_eq!(Random::new(0).next::<f32>(), 0.80145925);
-        assert_eq!(Random::new(0).next::<f64>(), 0.7311134158637046);
+        assert_eq!(Random::new(0).next_u64(), 11091344671253066420);
+        assert_eq!(Random::new(0).next::<i32>(), -881462604);
+        assert_eq!(Random::new(0).next::<u32>(), 3413504692);
+        assert_eq!(Random::new(0).next::<f32>(), 0.601263);
+        assert_eq!(Random::new(0).next::<f64>(), 0.6012629994179048);
         assert_eq!(Random::new(0).next::<bool>(), true);
-        assert_eq!(Random::new(0).next::<char>(), '\u{da18a}');
+        assert_eq!(Random::new(0).next::<char>(), '\u{b9d2a}');
+        assert_eq!(Random::new(0).next_string(10), "YeYpzpj4KT");
+    }
+
+    #[test]
+    fn test_next_range_and_shuffle_to_be_deterministic() {
+        assert_eq!(Random::new(0).next_range(1, 100), 3);
+        assert_eq!(
+            Random::new(0).next_range(-1_000_000_000i64, 1_000_000_000),
+            -714814251
+        );
+        assert_eq!(Random::new(0).next_range(-2.5, 2.5), 0.5063149970895244);
+        let mut v: Vec<i32> = (0..10).collect();
+        Random::new(0).shuffle(&mut v);
+        assert_eq!(v, [1, 4, 0, 3, 7, 6, 5, 9, 8, 2]);
+    }
+
+    #[test]
+    fn test_next_range_is_uniform_over_whole_range() {
+        let mut rnd = Random::new(0);
+        assert_eq!(rnd.next_range(5, 5), 5);
+        assert_eq!(rnd.next_range(-3i8, -3), -3);
+        let mut counts = [0; 3];
+        for _ in 0..3000 {
+            counts[(rnd.next_range(-1i64, 1) + 1) as usize] += 1;
+        }
+        assert!(counts.iter().all(|&count| 900 < count && count < 1100));
+        rnd.next_range(i64::MIN, i64::MAX);
+        rnd.next_range(i128::MIN, i128::MAX);
+        rnd.next_range(u128::MIN, u128::MAX);
+        let x = rnd.next_range(-2.5, 2.5);
+        assert!((-2.5..=2.5).contains(&x));
     }
 
     #[test]
@@ -71,6 +357,76 @@ mod tests {
         assert_eq!(Random::new(0).next_string(2).len(), 2);
     }
 
+    #[test]
+    fn test_shuffle_keeps_elements() {
+        let mut rnd = Random::new(0);
+        let mut v: Vec<i32> = (0..100).collect();
+        rnd.shuffle(&mut v);
+        assert_ne!(v, (0..100).collect::<Vec<i32>>());
+        v.sort_unstable();
+        assert_eq!(v, (0..100).collect::<Vec<i32>>());
+    }
+
+    #[test]
+    fn test_permutation() {
+        let mut p = Random::new(0).permutation(50);
+        assert_eq!(p, Random::new(0).permutation(50));
+        p.sort_unstable();
+        assert_eq!(p, (1..=50).collect::<Vec<usize>>());
+        assert!(Random::new(0).permutation(0).is_empty());
+    }
+
+    #[test]
+    fn test_choose() {
+        let mut rnd = Random::new(0);
+        assert_eq!(rnd.choose(&[] as &[i32]), None);
+        assert_eq!(rnd.choose(&[7]), Some(&7));
+        let v = [1, 2, 3];
+        assert!(v.contains(rnd.choose(&v).unwrap()));
+    }
+
+    #[test]
+    fn test_sample_distinct() {
+        let mut rnd = Random::new(0);
+        let mut v = rnd.sample_distinct(10, 1, 10);
+        v.sort_unstable();
+        assert_eq!(v, (1..=10).collect::<Vec<i64>>());
+
+        let v = rnd.sample_distinct(1000, -1_000_000_000_000, 1_000_000_000_000);
+        assert_eq!(v.len(), 1000);
+        assert!(v
+            .iter()
+            .all(|x| (-1_000_000_000_000..=1_000_000_000_000).contains(x)));
+        assert_eq!(v.iter().collect::<HashSet<_>>().len(), 1000);
+
+        let v = rnd.sample_distinct(3, i64::MIN, i64::MAX);
+        assert_eq!(v.iter().collect::<HashSet<_>>().len(), 3);
+        assert!(rnd.sample_distinct(0, 5, 4).is_empty());
+    }
+
+    #[test]
+    fn test_weighted_choice() {
+        let mut rnd = Random::new(0);
+        assert_eq!(rnd.weighted_choice(&[0.0, 1.0, 0.0]), 1);
+        let mut counts = [0; 2];
+        for _ in 0..1000 {
+            counts[rnd.weighted_choice(&[1.0, 9.0])] += 1;
+        }
+        assert!(counts[0] < counts[1]);
+    }
+
+    #[test]
+    fn test_derive() {
+        let mut rnd = Random::new(0);
+        let before: u64 = rnd.derive(1).next();
+        rnd.next::<u64>();
+        assert_eq!(rnd.derive(1).next::<u64>(), before);
+        assert_eq!(rnd.derive(1).seed(), derive_seed(0, 1));
+        assert_ne!(derive_seed(0, 1), derive_seed(0, 2));
+        assert_ne!(derive_seed(0, 1), derive_seed(1, 1));
+        assert_ne!(rnd.derive(2).next::<u64>(), before);
+    }
+
     #[test]
     fn test_next_string_returns_random_string() {
         let mut rnd = Random::new(0);
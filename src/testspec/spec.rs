@@ -2,26 +2,67 @@ use crate::problemspec::spec::{MultitaskProblemSpec, ProblemSpec};
 
 use super::random::Random;
 
+/// Test cases that each draw from their own `Random`: the `i`-th one added gets stream `i` of
+/// the generator the runner hands out. Editing one test case leaves the others unchanged, and
+/// any of them can be generated again from the seed the manifest records for it.
+pub struct TestCases<T> {
+    random: Random,
+    specs: Vec<T>,
+    seeds: Vec<u64>,
+}
+
+impl<T> TestCases<T> {
+    pub(crate) fn new(random: Random) -> Self {
+        TestCases {
+            random,
+            specs: Vec::new(),
+            seeds: Vec::new(),
+        }
+    }
+
+    /// Adds the test case `generate` makes from a `Random` of its own.
+    pub fn add(&mut self, generate: impl FnOnce(&mut Random) -> T) {
+        let mut random = self.random.derive(self.specs.len() as u64);
+        self.seeds.push(random.seed());
+        self.specs.push(generate(&mut random));
+    }
+
+    /// Seed of the generator every test case's stream is derived from.
+    pub(crate) fn seed(&self) -> u64 {
+        self.random.seed()
+    }
+
+    pub(crate) fn specs(&self) -> &[T] {
+        &self.specs
+    }
+
+    /// Seed of the `Random` each test case was generated from.
+    pub(crate) fn seeds(&self) -> &[u64] {
+        &self.seeds
+    }
+}
+
 pub trait SingletaskTestSpec<T>
 where
     T: ProblemSpec<T>,
 {
     fn sample_test_cases() -> Vec<T>;
-    fn test_cases(random: &mut Random) -> Vec<T>;
+    fn test_cases(tests: TestCases<T>) -> TestCases<T>;
 }
 
+/// Each subtask gets its own `TestCases`, derived from the seed and the subtask number.
 pub trait MultitaskTestSpec<T>
 where
     T: ProblemSpec<T> + MultitaskProblemSpec<T>,
 {
     fn sample_test_cases() -> Vec<T>;
-    fn test_cases_subtask_1(_random: &mut Random) -> Option<Vec<T>> {
+    fn test_cases_subtask_1(_tests: TestCases<T>) -> Option<TestCases<T>> {
         None
     }
-    fn test_cases_subtask_2(_random: &mut Random) -> Option<Vec<T>> {
+    fn test_cases_subtask_2(_tests: TestCases<T>) -> Option<TestCases<T>> {
         None
     }
-    fn test_cases_subtask_3(_random: &mut Random) -> Option<Vec<T>> {
+    fn test_cases_subtask_3(_tests: TestCases<T>) -> Option<TestCases<T>> {
         None
     }
 }
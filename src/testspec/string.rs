@@ -0,0 +1,350 @@
+use std::{iter::Peekable, str::Chars};
+
+use super::random::Random;
+
+pub const LOWERCASE: &str = "abcdefghijklmnopqrstuvwxyz";
+pub const UPPERCASE: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+pub const DIGITS: &str = "0123456789";
+
+/// How many extra repetitions `*`, `+` and `{n,}` may produce in `string_matching`.
+const MAX_EXTRA_REPETITIONS: usize = 8;
+
+/// String generators.
+impl Random {
+    pub fn string_over(&mut self, len: usize, alphabet: &str) -> String {
+        let alphabet: Vec<char> = alphabet.chars().collect();
+        assert!(!alphabet.is_empty(), "alphabet must not be empty");
+        (0..len).map(|_| *self.choose(&alphabet).unwrap()).collect()
+    }
+
+    pub fn lowercase_string(&mut self, len: usize) -> String {
+        self.string_over(len, LOWERCASE)
+    }
+
+    /// A random block of length `period` repeated up to length `len`.
+    pub fn periodic_string(&mut self, len: usize, period: usize, alphabet: &str) -> String {
+        assert!(period > 0, "period must be positive");
+        let block: Vec<char> = self.string_over(period, alphabet).chars().collect();
+        (0..len).map(|i| block[i % period]).collect()
+    }
+
+    pub fn palindrome(&mut self, len: usize, alphabet: &str) -> String {
+        let half: Vec<char> = self.string_over(len - len / 2, alphabet).chars().collect();
+        half.iter().chain(half.iter().rev().skip(len % 2)).collect()
+    }
+
+    /// A random string with `occurrences` non-overlapping copies of `pattern` at random
+    /// positions. The rest of the string may contain more occurrences by chance.
+    pub fn string_with_pattern(
+        &mut self,
+        len: usize,
+        pattern: &str,
+        occurrences: usize,
+        alphabet: &str,
+    ) -> String {
+        let pattern_len = pattern.chars().count();
+        assert!(
+            occurrences * pattern_len <= len,
+            "{} copies of {:?} do not fit in {} characters",
+            occurrences,
+            pattern,
+            len
+        );
+        // Lay out `occurrences` patterns and `free` single characters in a random order.
+        let free = len - occurrences * pattern_len;
+        let mut is_pattern = vec![false; free + occurrences];
+        for i in self.sample_distinct(occurrences, 0, (free + occurrences) as i64 - 1) {
+            is_pattern[i as usize] = true;
+        }
+        let mut result = String::with_capacity(len);
+        for is_pattern in is_pattern {
+            if is_pattern {
+                result.push_str(pattern);
+            } else {
+                result.push_str(&self.string_over(1, alphabet));
+            }
+        }
+        result
+    }
+
+    /// A random string matching a simple regular expression. Supported: literals, `.`,
+    /// escapes (`\d`, `\w`, `\s` and escaped special characters), classes such as `[a-z0-9_]`
+    /// and `[^abc]`, groups with `|`, and the quantifiers `*`, `+`, `?`, `{n}`, `{n,}` and
+    /// `{n,m}`. `.` only produces ASCII letters and digits, and `\s` only a space. Panics on
+    /// anything else.
+    pub fn string_matching(&mut self, regex: &str) -> String {
+        let mut chars = regex.chars().peekable();
+        let alternatives = parse_alternatives(&mut chars);
+        if let Some(c) = chars.next() {
+            panic!("unexpected {:?} in regex {:?}", c, regex);
+        }
+        let mut result = String::new();
+        self.generate_alternatives(&alternatives, &mut result);
+        result
+    }
+
+    fn generate_alternatives(&mut self, alternatives: &[Vec<Repeat>], result: &mut String) {
+        let sequence = self.choose(alternatives).unwrap();
+        for repeat in sequence {
+            for _ in 0..self.next_range(repeat.min, repeat.max) {
+                match &repeat.node {
+                    Node::Chars(chars) => result.push(*self.choose(chars).unwrap()),
+                    Node::Group(alternatives) => self.generate_alternatives(alternatives, result),
+                }
+            }
+        }
+    }
+}
+
+/// Prefix of the Fibonacci word `abaababaabaab...`, which breaks many hashing and
+/// periodicity heuristics.
+pub fn fibonacci_string(len: usize) -> String {
+    let (mut previous, mut current) = ("a".to_string(), "ab".to_string());
+    while current.len() < len {
+        let next = current.clone() + &previous;
+        previous = current;
+        current = next;
+    }
+    current.truncate(len);
+    current
+}
+
+/// Prefix of the Thue-Morse sequence over `ab`. For `len = 2^k` with `k >= 11`, the result and
+/// its complement (`a` and `b` swapped) collide under polynomial hashing modulo `2^64` with any
+/// odd base.
+pub fn thue_morse(len: usize) -> String {
+    (0..len)
+        .map(|i: usize| if i.count_ones() & 1 == 0 { 'a' } else { 'b' })
+        .collect()
+}
+
+enum Node {
+    Chars(Vec<char>),
+    Group(Vec<Vec<Repeat>>),
+}
+
+struct Repeat {
+    node: Node,
+    min: usize,
+    max: usize,
+}
+
+fn printable() -> impl Iterator<Item = char> {
+    ' '..='~'
+}
+
+fn parse_alternatives(chars: &mut Peekable<Chars>) -> Vec<Vec<Repeat>> {
+    let mut alternatives = vec![parse_sequence(chars)];
+    while chars.peek() == Some(&'|') {
+        chars.next();
+        alternatives.push(parse_sequence(chars));
+    }
+    alternatives
+}
+
+fn parse_sequence(chars: &mut Peekable<Chars>) -> Vec<Repeat> {
+    let mut sequence = Vec::new();
+    while let Some(&c) = chars.peek() {
+        if c == '|' || c == ')' {
+            break;
+        }
+        chars.next();
+        let node = match c {
+            '(' => {
+                let group = parse_alternatives(chars);
+                assert_eq!(chars.next(), Some(')'), "unclosed group in regex");
+                Node::Group(group)
+            }
+            '[' => Node::Chars(parse_class(chars)),
+            '.' => Node::Chars(
+                format!("{}{}{}", LOWERCASE, UPPERCASE, DIGITS)
+                    .chars()
+                    .collect(),
+            ),
+            '\\' => Node::Chars(parse_escape(chars)),
+            '*' | '+' | '?' | '{' => panic!("nothing to repeat before {:?} in regex", c),
+            c => Node::Chars(vec![c]),
+        };
+        let (min, max) = parse_quantifier(chars);
+        sequence.push(Repeat { node, min, max });
+    }
+    sequence
+}
+
+fn parse_escape(chars: &mut Peekable<Chars>) -> Vec<char> {
+    match chars.next() {
+        Some('d') => DIGITS.chars().collect(),
+        Some('w') => format!("{}{}{}_", LOWERCASE, UPPERCASE, DIGITS)
+            .chars()
+            .collect(),
+        Some('s') => vec![' '],
+        Some(c) => vec![c],
+        None => panic!("regex ends with an escape"),
+    }
+}
+
+fn parse_class(chars: &mut Peekable<Chars>) -> Vec<char> {
+    let negated = chars.peek() == Some(&'^');
+    if negated {
+        chars.next();
+    }
+    let mut members = Vec::new();
+    loop {
+        let c = match chars.next() {
+            Some(']') => break,
+            Some('\\') => {
+                members.extend(parse_escape(chars));
+                continue;
+            }
+            Some(c) => c,
+            None => panic!("unclosed character class in regex"),
+        };
+        let mut lookahead = chars.clone();
+        match (lookahead.next(), lookahead.next()) {
+            (Some('-'), Some(end)) if end != ']' => {
+                chars.next();
+                chars.next();
+                assert!(c <= end, "invalid range {}-{} in regex", c, end);
+                members.extend(c..=end);
+            }
+            _ => members.push(c),
+        }
+    }
+    let members: Vec<char> = if negated {
+        printable().filter(|c| !members.contains(c)).collect()
+    } else {
+        members
+    };
+    assert!(!members.is_empty(), "empty character class in regex");
+    members
+}
+
+fn parse_number(chars: &mut Peekable<Chars>) -> Option<usize> {
+    let mut digits = String::new();
+    while let Some(c) = chars.peek().filter(|c| c.is_ascii_digit()) {
+        digits.push(*c);
+        chars.next();
+    }
+    digits.parse().ok()
+}
+
+fn parse_quantifier(chars: &mut Peekable<Chars>) -> (usize, usize) {
+    match chars.peek() {
+        Some('*') => {
+            chars.next();
+            (0, MAX_EXTRA_REPETITIONS)
+        }
+        Some('+') => {
+            chars.next();
+            (1, 1 + MAX_EXTRA_REPETITIONS)
+        }
+        Some('?') => {
+            chars.next();
+            (0, 1)
+        }
+        Some('{') => {
+            chars.next();
+            let min = parse_number(chars).expect("expected a number after { in regex");
+            let max = if chars.peek() == Some(&',') {
+                chars.next();
+                parse_number(chars).unwrap_or(min + MAX_EXTRA_REPETITIONS)
+            } else {
+                min
+            };
+            assert_eq!(chars.next(), Some('}'), "unclosed {{ in regex");
+            assert!(
+                min <= max,
+                "invalid repetition {{{},{}}} in regex",
+                min,
+                max
+            );
+            (min, max)
+        }
+        _ => (1, 1),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::problemspec::predicates::over_alphabet;
+
+    #[test]
+    fn test_string_over() {
+        let mut rnd = Random::new(0);
+        let s = rnd.string_over(100, "xy");
+        assert_eq!(s.len(), 100);
+        assert!(over_alphabet(&s, "xy").is_ok());
+        assert!(over_alphabet(&rnd.lowercase_string(100), LOWERCASE).is_ok());
+    }
+
+    #[test]
+    #[should_panic(expected = "alphabet must not be empty")]
+    fn test_string_over_rejects_empty_alphabet() {
+        Random::new(0).string_over(1, "");
+    }
+
+    #[test]
+    fn test_periodic_string() {
+        let s = Random::new(0).periodic_string(10, 3, LOWERCASE);
+        assert_eq!(s.len(), 10);
+        assert_eq!(&s[0..3], &s[3..6]);
+        assert_eq!(&s[0..1], &s[9..10]);
+    }
+
+    #[test]
+    fn test_palindrome() {
+        let mut rnd = Random::new(0);
+        for len in 0..10 {
+            let s = rnd.palindrome(len, "abc");
+            assert_eq!(s.len(), len);
+            assert_eq!(s, s.chars().rev().collect::<String>());
+        }
+    }
+
+    #[test]
+    fn test_string_with_pattern() {
+        let mut rnd = Random::new(0);
+        let s = rnd.string_with_pattern(20, "xyz", 4, "ab");
+        assert_eq!(s.len(), 20);
+        assert_eq!(s.matches("xyz").count(), 4);
+        assert_eq!(rnd.string_with_pattern(6, "ab", 3, "c"), "ababab");
+    }
+
+    #[test]
+    fn test_fibonacci_string() {
+        assert_eq!(fibonacci_string(13), "abaababaabaab");
+        assert_eq!(fibonacci_string(0), "");
+    }
+
+    #[test]
+    fn test_thue_morse() {
+        assert_eq!(thue_morse(8), "abbabaab");
+    }
+
+    #[test]
+    fn test_string_matching() {
+        let mut rnd = Random::new(0);
+        assert_eq!(rnd.string_matching("abc"), "abc");
+        assert_eq!(rnd.string_matching("a{3}"), "aaa");
+        let s = rnd.string_matching("\\d{2}\\.x");
+        assert_eq!(s.len(), 4);
+        assert!(s.chars().take(2).all(|c| c.is_ascii_digit()));
+        assert!(s.ends_with(".x"));
+        for _ in 0..20 {
+            let s = rnd.string_matching("[a-c]{2,4}(x|yz)+[^a-z]?");
+            let letters = s.chars().take_while(|c| ('a'..='c').contains(c)).count();
+            assert!((2..=4).contains(&letters));
+            let rest = &s[letters..];
+            let groups = rest.trim_end_matches(|c: char| !c.is_ascii_lowercase());
+            assert!(!groups.is_empty());
+            assert!(groups.replace("yz", "").chars().all(|c| c == 'x'));
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_string_matching_rejects_invalid_regex() {
+        Random::new(0).string_matching("(ab");
+    }
+}
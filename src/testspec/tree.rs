@@ -0,0 +1,257 @@
+use super::random::Random;
+
+/// Edges between nodes numbered from 1.
+pub type Edges = Vec<(usize, usize)>;
+
+/// Tree generators. Every tree has nodes `1..=n` and is returned as `n - 1` edges
+/// `(parent, child)` with node 1 as the root and every parent smaller than its child, as are
+/// the fixed shapes `tree_path`, `tree_star` and `tree_broom`. Use `relabel` and
+/// `shuffle_edges` to hide that structure from solutions.
+impl Random {
+    /// Uniformly random labelled tree, decoded from a random Prüfer sequence.
+    pub fn tree(&mut self, n: usize) -> Edges {
+        if n <= 2 {
+            return tree_path(n);
+        }
+        let prufer: Vec<usize> = (0..n - 2).map(|_| self.next_range(1, n)).collect();
+        let mut degree = vec![1; n + 1];
+        for &x in &prufer {
+            degree[x] += 1;
+        }
+
+        let mut edges = Vec::with_capacity(n - 1);
+        let mut leaf = (1..=n).find(|&x| degree[x] == 1).unwrap();
+        let mut next_leaf = leaf;
+        for &x in &prufer {
+            edges.push((x, leaf));
+            degree[x] -= 1;
+            if x < next_leaf && degree[x] == 1 {
+                leaf = x;
+            } else {
+                next_leaf = (next_leaf + 1..=n).find(|&y| degree[y] == 1).unwrap();
+                leaf = next_leaf;
+            }
+        }
+        edges.push((leaf, n));
+        root_at_one(n, &edges)
+    }
+
+    /// A path `1 - ... - spine` with every other node attached to a random node of the path.
+    pub fn tree_caterpillar(&mut self, n: usize, spine: usize) -> Edges {
+        let spine = spine.max(1).min(n);
+        let mut edges = tree_path(spine);
+        for i in spine + 1..=n {
+            edges.push((self.next_range(1, spine), i));
+        }
+        edges
+    }
+
+    /// Random tree where every node has at most two children.
+    pub fn tree_binary(&mut self, n: usize) -> Edges {
+        let mut children = vec![0; n + 1];
+        let mut open = vec![1];
+        let mut edges = Vec::with_capacity(n.saturating_sub(1));
+        for i in 2..=n {
+            let index = self.next_range(0, open.len() - 1);
+            let parent = open[index];
+            edges.push((parent, i));
+            children[parent] += 1;
+            if children[parent] == 2 {
+                open.swap_remove(index);
+            }
+            open.push(i);
+        }
+        edges
+    }
+
+    /// Random tree where no node is deeper than `depth`, with the root at depth 0.
+    pub fn tree_bounded_depth(&mut self, n: usize, depth: usize) -> Edges {
+        assert!(
+            n <= 1 || depth > 0,
+            "a tree with more than one node needs depth > 0"
+        );
+        let mut depths = vec![0; n + 1];
+        let mut open = vec![1];
+        let mut edges = Vec::with_capacity(n.saturating_sub(1));
+        for i in 2..=n {
+            let parent = open[self.next_range(0, open.len() - 1)];
+            edges.push((parent, i));
+            depths[i] = depths[parent] + 1;
+            if depths[i] < depth {
+                open.push(i);
+            }
+        }
+        edges
+    }
+
+    /// Every node `i > 1` picks a parent among `1..i`. A positive `bias` prefers recent nodes,
+    /// giving deep trees, a negative one prefers early nodes, giving shallow trees, and 0
+    /// picks uniformly.
+    pub fn tree_random_parent(&mut self, n: usize, bias: i32) -> Edges {
+        (2..=n)
+            .map(|i| {
+                let mut parent = self.next_range(1, i - 1);
+                for _ in 0..bias.unsigned_abs() {
+                    let other = self.next_range(1, i - 1);
+                    parent = if bias > 0 {
+                        parent.max(other)
+                    } else {
+                        parent.min(other)
+                    };
+                }
+                (parent, i)
+            })
+            .collect()
+    }
+
+    /// Renames the nodes `1..=n` with a random permutation.
+    pub fn relabel(&mut self, n: usize, edges: &[(usize, usize)]) -> Edges {
+        let labels = self.permutation(n);
+        edges
+            .iter()
+            .map(|&(u, v)| (labels[u - 1], labels[v - 1]))
+            .collect()
+    }
+
+    /// Shuffles the order of the edges and the order of the two nodes of each edge.
+    pub fn shuffle_edges(&mut self, edges: &[(usize, usize)]) -> Edges {
+        let mut edges = edges.to_vec();
+        self.shuffle(&mut edges);
+        for edge in edges.iter_mut() {
+            if self.next::<bool>() {
+                *edge = (edge.1, edge.0);
+            }
+        }
+        edges
+    }
+}
+
+/// `1 - 2 - ... - n`
+pub fn tree_path(n: usize) -> Edges {
+    (2..=n).map(|i| (i - 1, i)).collect()
+}
+
+/// Node 1 connected to every other node.
+pub fn tree_star(n: usize) -> Edges {
+    (2..=n).map(|i| (1, i)).collect()
+}
+
+/// A path `1 - ... - handle` with every other node attached to its last node.
+pub fn tree_broom(n: usize, handle: usize) -> Edges {
+    let handle = handle.max(1).min(n);
+    let mut edges = tree_path(handle);
+    edges.extend((handle + 1..=n).map(|i| (handle, i)));
+    edges
+}
+
+/// Renumbers the tree in BFS order from node 1, so parents come before their children.
+fn root_at_one(n: usize, edges: &[(usize, usize)]) -> Edges {
+    let mut adjacency = vec![Vec::new(); n + 1];
+    for &(u, v) in edges {
+        adjacency[u].push(v);
+        adjacency[v].push(u);
+    }
+    let mut label = vec![0; n + 1];
+    let mut order = vec![1];
+    label[1] = 1;
+    let mut result = Vec::with_capacity(n - 1);
+    let mut head = 0;
+    while head < order.len() {
+        let u = order[head];
+        head += 1;
+        for &v in &adjacency[u] {
+            if label[v] == 0 {
+                order.push(v);
+                label[v] = order.len();
+                result.push((label[u], label[v]));
+            }
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::problemspec::predicates::is_tree;
+
+    fn depth(n: usize, edges: &[(usize, usize)]) -> usize {
+        let mut depths = vec![0; n + 1];
+        for &(parent, child) in edges {
+            depths[child] = depths[parent] + 1;
+        }
+        depths.into_iter().max().unwrap()
+    }
+
+    fn children(n: usize, edges: &[(usize, usize)]) -> Vec<usize> {
+        let mut children = vec![0; n + 1];
+        for &(parent, _) in edges {
+            children[parent] += 1;
+        }
+        children
+    }
+
+    #[test]
+    fn test_trees_are_trees() {
+        let mut rnd = Random::new(0);
+        for n in 1..30 {
+            assert!(is_tree(n, &rnd.tree(n)).is_ok());
+            assert!(is_tree(n, &tree_path(n)).is_ok());
+            assert!(is_tree(n, &tree_star(n)).is_ok());
+            assert!(is_tree(n, &rnd.tree_caterpillar(n, n / 2)).is_ok());
+            assert!(is_tree(n, &rnd.tree_binary(n)).is_ok());
+            assert!(is_tree(n, &tree_broom(n, n / 3)).is_ok());
+            assert!(is_tree(n, &rnd.tree_bounded_depth(n, 2)).is_ok());
+            assert!(is_tree(n, &rnd.tree_random_parent(n, 3)).is_ok());
+            assert!(is_tree(n, &rnd.tree_random_parent(n, -3)).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_trees_are_rooted_at_one() {
+        let mut rnd = Random::new(0);
+        for edges in [
+            rnd.tree(50),
+            rnd.tree_binary(50),
+            rnd.tree_random_parent(50, 0),
+        ] {
+            assert!(edges.iter().all(|(parent, child)| parent < child));
+        }
+    }
+
+    #[test]
+    fn test_tree_is_deterministic() {
+        assert_eq!(Random::new(1).tree(100), Random::new(1).tree(100));
+        assert_ne!(Random::new(1).tree(100), Random::new(2).tree(100));
+    }
+
+    #[test]
+    fn test_tree_shapes() {
+        let mut rnd = Random::new(0);
+        assert_eq!(tree_path(4), vec![(1, 2), (2, 3), (3, 4)]);
+        assert_eq!(tree_star(4), vec![(1, 2), (1, 3), (1, 4)]);
+        assert_eq!(tree_broom(5, 3), vec![(1, 2), (2, 3), (3, 4), (3, 5)]);
+        assert!(rnd
+            .tree_caterpillar(20, 5)
+            .iter()
+            .all(|&(parent, _)| parent <= 5));
+        assert!(children(100, &rnd.tree_binary(100)).iter().all(|&c| c <= 2));
+        assert_eq!(depth(100, &rnd.tree_bounded_depth(100, 3)), 3);
+        assert!(
+            depth(100, &rnd.tree_random_parent(100, 5))
+                > depth(100, &rnd.tree_random_parent(100, -5))
+        );
+    }
+
+    #[test]
+    fn test_relabel_and_shuffle_edges_keep_the_tree() {
+        let mut rnd = Random::new(0);
+        let edges = tree_path(20);
+        let relabeled = rnd.relabel(20, &edges);
+        assert!(is_tree(20, &relabeled).is_ok());
+        assert_ne!(relabeled, edges);
+        let shuffled = rnd.shuffle_edges(&edges);
+        assert!(is_tree(20, &shuffled).is_ok());
+        assert_ne!(shuffled, edges);
+    }
+}
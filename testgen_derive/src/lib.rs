@@ -0,0 +1,589 @@
+//! `#[derive(ProblemSpec)]` for testgen.
+//!
+//! Fields are laid out with attributes instead of hand-written
+//! `input_format`/`output_format` implementations:
+//!
+//! * `#[line(k)]`: the field is printed on line `k`; fields sharing `k` are joined with spaces
+//! * `#[bounded(n)]`: a `Vec` printed one element per line, `n` lines; adjacent fields with the
+//!   same `n` are printed side by side
+//! * `#[grid(h, w)]`: a `Vec<Vec<_>>` printed as an `h` x `w` grid
+//! * `#[output]`: the field belongs to the output instead of the input. Combine it with one of the
+//!   attributes above, otherwise it is printed on its own line. `Option` fields are unwrapped.
+//! * `#[constraint(expr)]`: on the struct or on a field, `expr` may refer to fields by name
+//!
+//! On the struct, `#[multiple_testcases(expr)]` (where `expr` may refer to `t`) and
+//! `#[output_prefix("Case #{}: ")]` fill in `multiple_test_case_config`, and
+//! `#[file_constraint(expr)]` (where `expr` may refer to `specs`, the test cases in one file)
+//! fills in `file_constraints`. `#[naming(template = "...", input_extension = "...",
+//! output_extension = "...")]`, all optional, fills in `naming_config`, and
+//! `#[problem(slug = "...", title = "...", time_limit = 1000, memory_limit = 256,
+//! total_score = 100, checker = "tokens")]`, also all optional, fills in `metadata`.
+//!
+//! Fields without any layout attribute are ignored.
+//!
+//! Only the generation side of `ProblemSpec` is derived: testgen has no input parser to target
+//! yet, so reading a spec back from an input file is not supported.
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2, TokenTree};
+use quote::quote;
+use syn::{
+    parse_macro_input, punctuated::Punctuated, Attribute, Data, DeriveInput, Error, Expr, Fields,
+    GenericArgument, Ident, Lit, LitInt, LitStr, MetaNameValue, PathArguments, Token, Type,
+};
+
+#[proc_macro_derive(
+    ProblemSpec,
+    attributes(
+        line,
+        bounded,
+        grid,
+        output,
+        constraint,
+        file_constraint,
+        multiple_testcases,
+        naming,
+        output_prefix,
+        problem
+    )
+)]
+pub fn derive_problem_spec(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+enum Layout {
+    Line(u64),
+    OwnLine,
+    Bounded(Box<Expr>),
+    Grid(Box<Expr>, Box<Expr>),
+}
+
+struct Field {
+    ident: Ident,
+    ty: Type,
+    layout: Option<Layout>,
+    output: bool,
+}
+
+enum Element<'a> {
+    Line(Option<u64>, Vec<&'a Field>),
+    Bounded(String, &'a Expr, Vec<&'a Field>),
+    Grid(&'a Field, &'a Expr, &'a Expr),
+}
+
+fn expand(input: &DeriveInput) -> Result<TokenStream2, Error> {
+    let name = &input.ident;
+    let named = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(named) => &named.named,
+            _ => {
+                return Err(Error::new_spanned(
+                    name,
+                    "ProblemSpec can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(Error::new_spanned(
+                name,
+                "ProblemSpec can only be derived for structs",
+            ))
+        }
+    };
+
+    let mut fields = Vec::new();
+    let mut constraints = Vec::new();
+    for field in named {
+        let ident = field.ident.clone().unwrap();
+        let mut layout = None;
+        let mut output = false;
+        for attr in &field.attrs {
+            if attr.path.is_ident("output") {
+                output = true;
+                continue;
+            }
+            if attr.path.is_ident("constraint") {
+                constraints.push(attr.parse_args::<Expr>()?);
+                continue;
+            }
+            let parsed = if attr.path.is_ident("line") {
+                Layout::Line(attr.parse_args::<LitInt>()?.base10_parse()?)
+            } else if attr.path.is_ident("bounded") {
+                Layout::Bounded(Box::new(attr.parse_args::<Expr>()?))
+            } else if attr.path.is_ident("grid") {
+                let mut sizes = parse_exprs(attr)?.into_iter();
+                match (sizes.next(), sizes.next(), sizes.next()) {
+                    (Some(height), Some(width), None) => {
+                        Layout::Grid(Box::new(height), Box::new(width))
+                    }
+                    _ => return Err(Error::new_spanned(attr, "expected #[grid(height, width)]")),
+                }
+            } else {
+                continue;
+            };
+            if layout.is_some() {
+                return Err(Error::new_spanned(
+                    attr,
+                    "a field can only have one of #[line], #[bounded] or #[grid]",
+                ));
+            }
+            layout = Some(parsed);
+        }
+        if output && layout.is_none() {
+            layout = Some(Layout::OwnLine);
+        }
+        fields.push(Field {
+            ident,
+            ty: field.ty.clone(),
+            layout,
+            output,
+        });
+    }
+
+    let mut file_constraints = Vec::new();
+    let mut testcases_constraints = None;
+    let mut output_prefix = None;
+    let mut naming = None;
+    let mut metadata = None;
+    for attr in &input.attrs {
+        if attr.path.is_ident("constraint") {
+            constraints.push(attr.parse_args::<Expr>()?);
+        } else if attr.path.is_ident("file_constraint") {
+            file_constraints.push(attr.parse_args::<Expr>()?);
+        } else if attr.path.is_ident("multiple_testcases") {
+            testcases_constraints = Some(if attr.tokens.is_empty() {
+                Vec::new()
+            } else {
+                parse_exprs(attr)?
+            });
+        } else if attr.path.is_ident("output_prefix") {
+            output_prefix = Some(attr.parse_args::<LitStr>()?);
+        } else if attr.path.is_ident("naming") {
+            naming = Some(naming_config(attr)?);
+        } else if attr.path.is_ident("problem") {
+            metadata = Some(problem_metadata(attr)?);
+        }
+    }
+
+    let input_format = format_body(&fields, false);
+    let output_format = format_body(&fields, true);
+    let constraints_body = constraints_body(&fields, &constraints);
+    let file_constraints = if file_constraints.is_empty() {
+        quote! {}
+    } else {
+        quote! {
+            fn file_constraints(
+                specs: &[#name],
+            ) -> ::std::result::Result<(), ::testgen::problemspec::spec::ConstraintsError> {
+                ::testgen::CONS!(#(#file_constraints),*)
+            }
+        }
+    };
+    let multiple_test_case_config = if testcases_constraints.is_some() || output_prefix.is_some() {
+        let testcases_constraints = match testcases_constraints {
+            Some(exprs) if !exprs.is_empty() => quote! { |t| ::testgen::CONS!(#(#exprs),*) },
+            _ => quote! { |_| Ok(()) },
+        };
+        let output_prefix = match output_prefix {
+            Some(prefix) => quote! { Some(::std::string::String::from(#prefix)) },
+            None => quote! { None },
+        };
+        quote! {
+            fn multiple_test_case_config(
+            ) -> Option<::testgen::problemspec::spec::MultipleTestcaseConfig> {
+                Some(::testgen::problemspec::spec::MultipleTestcaseConfig {
+                    constraints: #testcases_constraints,
+                    output_prefix: #output_prefix,
+                })
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    Ok(quote! {
+        impl ::testgen::problemspec::spec::ProblemSpec<#name> for #name {
+            fn input_format(&self) -> ::testgen::problemspec::spec::IOFormat {
+                #input_format
+            }
+
+            fn output_format(&self) -> ::testgen::problemspec::spec::IOFormat {
+                #output_format
+            }
+
+            fn constraints(
+                &self,
+            ) -> ::std::result::Result<(), ::testgen::problemspec::spec::ConstraintsError> {
+                #constraints_body
+            }
+
+            #file_constraints
+
+            #multiple_test_case_config
+
+            #naming
+
+            #metadata
+        }
+    })
+}
+
+fn name_values(attr: &Attribute) -> Result<Punctuated<MetaNameValue, Token![,]>, Error> {
+    attr.parse_args_with(Punctuated::<MetaNameValue, Token![,]>::parse_terminated)
+}
+
+fn lit_str(lit: &Lit) -> Result<LitStr, Error> {
+    match lit {
+        Lit::Str(value) => Ok(value.clone()),
+        lit => Err(Error::new_spanned(lit, "expected a string")),
+    }
+}
+
+fn lit_int(lit: &Lit) -> Result<LitInt, Error> {
+    match lit {
+        Lit::Int(value) => Ok(value.clone()),
+        lit => Err(Error::new_spanned(lit, "expected an integer")),
+    }
+}
+
+fn option<T: quote::ToTokens>(value: Option<T>, wrap: fn(T) -> TokenStream2) -> TokenStream2 {
+    match value {
+        Some(value) => {
+            let value = wrap(value);
+            quote! { Some(#value) }
+        }
+        None => quote! { None },
+    }
+}
+
+fn string(value: LitStr) -> TokenStream2 {
+    quote! { ::std::string::String::from(#value) }
+}
+
+fn naming_config(attr: &Attribute) -> Result<TokenStream2, Error> {
+    let (mut template, mut input_extension, mut output_extension) = (None, None, None);
+    for pair in name_values(attr)? {
+        let value = lit_str(&pair.lit)?;
+        if pair.path.is_ident("template") {
+            template = Some(value);
+        } else if pair.path.is_ident("input_extension") {
+            input_extension = Some(value);
+        } else if pair.path.is_ident("output_extension") {
+            output_extension = Some(value);
+        } else {
+            return Err(Error::new_spanned(
+                &pair.path,
+                "expected template, input_extension or output_extension",
+            ));
+        }
+    }
+    let (template, input_extension, output_extension) = (
+        option(template, string),
+        option(input_extension, string),
+        option(output_extension, string),
+    );
+    Ok(quote! {
+        fn naming_config() -> ::testgen::problemspec::spec::NamingConfig {
+            ::testgen::problemspec::spec::NamingConfig {
+                template: #template,
+                input_extension: #input_extension,
+                output_extension: #output_extension,
+            }
+        }
+    })
+}
+
+fn problem_metadata(attr: &Attribute) -> Result<TokenStream2, Error> {
+    let (mut slug, mut title, mut checker) = (None, None, None);
+    let (mut time_limit, mut memory_limit, mut total_score) = (None, None, None);
+    for pair in name_values(attr)? {
+        let path = &pair.path;
+        if path.is_ident("slug") {
+            slug = Some(lit_str(&pair.lit)?);
+        } else if path.is_ident("title") {
+            title = Some(lit_str(&pair.lit)?);
+        } else if path.is_ident("checker") {
+            checker = Some(lit_str(&pair.lit)?);
+        } else if path.is_ident("time_limit") {
+            time_limit = Some(lit_int(&pair.lit)?);
+        } else if path.is_ident("memory_limit") {
+            memory_limit = Some(lit_int(&pair.lit)?);
+        } else if path.is_ident("total_score") {
+            total_score = Some(lit_int(&pair.lit)?);
+        } else {
+            return Err(Error::new_spanned(
+                path,
+                "expected slug, title, time_limit, memory_limit, total_score or checker",
+            ));
+        }
+    }
+    let (slug, title) = (option(slug, string), option(title, string));
+    let number = |value: LitInt| quote! { #value };
+    let (time_limit, memory_limit, total_score) = (
+        option(time_limit, number),
+        option(memory_limit, number),
+        option(total_score, number),
+    );
+    let checker = match checker {
+        Some(checker) => {
+            let checker = checker_kind(&checker)?;
+            quote! { Some(#checker) }
+        }
+        None => quote! { None },
+    };
+    Ok(quote! {
+        fn metadata() -> ::testgen::problemspec::spec::ProblemMetadata {
+            ::testgen::problemspec::spec::ProblemMetadata {
+                slug: #slug,
+                title: #title,
+                time_limit: #time_limit,
+                memory_limit: #memory_limit,
+                total_score: #total_score,
+                checker: #checker,
+            }
+        }
+    })
+}
+
+/// Parses `checker` like `CheckerKind::from_str`, so a bad float error fails to compile.
+fn checker_kind(checker: &LitStr) -> Result<TokenStream2, Error> {
+    let value = checker.value();
+    let kind = quote! { ::testgen::problemspec::spec::CheckerKind };
+    Ok(match value.as_str() {
+        "exact" => quote! { #kind::Exact },
+        "tokens" => quote! { #kind::Tokens },
+        "float" => quote! { #kind::Float(1e-6) },
+        _ => match value.strip_prefix("float:") {
+            Some(error) => {
+                let error = error
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|error| error.is_finite())
+                    .ok_or_else(|| {
+                        Error::new_spanned(
+                            checker,
+                            format!("invalid float checker error {:?}", error),
+                        )
+                    })?;
+                let error = proc_macro2::Literal::f64_unsuffixed(error);
+                quote! { #kind::Float(#error) }
+            }
+            None => quote! { #kind::Custom(::std::string::String::from(#checker)) },
+        },
+    })
+}
+
+fn parse_exprs(attr: &Attribute) -> Result<Vec<Expr>, Error> {
+    Ok(attr
+        .parse_args_with(Punctuated::<Expr, Token![,]>::parse_terminated)?
+        .into_iter()
+        .collect())
+}
+
+fn format_body(fields: &[Field], output: bool) -> TokenStream2 {
+    let mut elements: Vec<Element> = Vec::new();
+    for field in fields.iter().filter(|field| field.output == output) {
+        match &field.layout {
+            Some(Layout::Line(index)) => {
+                let existing = elements.iter_mut().find_map(|element| match element {
+                    Element::Line(Some(other), line) if other == index => Some(line),
+                    _ => None,
+                });
+                match existing {
+                    Some(line) => line.push(field),
+                    None => elements.push(Element::Line(Some(*index), vec![field])),
+                }
+            }
+            Some(Layout::OwnLine) => elements.push(Element::Line(None, vec![field])),
+            Some(Layout::Bounded(size)) => {
+                let key = quote!(#size).to_string();
+                match elements.last_mut() {
+                    Some(Element::Bounded(other, _, columns)) if *other == key => {
+                        columns.push(field)
+                    }
+                    _ => elements.push(Element::Bounded(key, size, vec![field])),
+                }
+            }
+            Some(Layout::Grid(height, width)) => elements.push(Element::Grid(field, height, width)),
+            None => {}
+        }
+    }
+
+    let sizes: Vec<&Expr> = elements
+        .iter()
+        .flat_map(|element| match element {
+            Element::Line(..) => vec![],
+            Element::Bounded(_, size, _) => vec![*size],
+            Element::Grid(_, height, width) => vec![*height, *width],
+        })
+        .collect();
+    let bindings = bindings(fields, &sizes);
+
+    let elements = elements.iter().map(|element| match element {
+        Element::Line(_, line) => {
+            let contents = line.iter().map(|field| {
+                let value = access(field);
+                if is_vec(inner_type(&field.ty)) {
+                    quote! {
+                        ::testgen::problemspec::spec::LineElement::UnboundedVec(#value
+                            .iter()
+                            .map(|x| ::std::convert::Into::<::testgen::problemspec::spec::Scalar>::into(x.clone()))
+                            .collect())
+                    }
+                } else {
+                    quote! {
+                        ::testgen::problemspec::spec::LineElement::Scalar(
+                            ::std::convert::Into::<::testgen::problemspec::spec::Scalar>::into(
+                                ::std::clone::Clone::clone(#value),
+                            ),
+                        )
+                    }
+                }
+            });
+            quote! { ::testgen::problemspec::spec::IOElement::Line(vec![#(#contents),*]) }
+        }
+        Element::Bounded(_, size, columns) => {
+            let columns = columns.iter().map(|field| {
+                let value = access(field);
+                quote! {
+                    #value
+                        .iter()
+                        .map(|x| ::std::convert::Into::<::testgen::problemspec::spec::Scalar>::into(x.clone()))
+                        .collect()
+                }
+            });
+            quote! {
+                ::testgen::problemspec::spec::IOElement::LinesBounded(
+                    vec![#(#columns),*],
+                    (#size) as usize,
+                )
+            }
+        }
+        Element::Grid(field, height, width) => {
+            let value = access(field);
+            quote! {
+                ::testgen::problemspec::spec::IOElement::Grid(
+                    #value
+                        .iter()
+                        .map(|row| {
+                            row.iter()
+                                .map(|x| ::std::convert::Into::<::testgen::problemspec::spec::Scalar>::into(x.clone()))
+                                .collect()
+                        })
+                        .collect(),
+                    (#height) as usize,
+                    (#width) as usize,
+                )
+            }
+        }
+    });
+
+    quote! {
+        #bindings
+        vec![#(#elements),*]
+    }
+}
+
+fn constraints_body(fields: &[Field], constraints: &[Expr]) -> TokenStream2 {
+    if constraints.is_empty() {
+        return quote! { Ok(()) };
+    }
+    let exprs: Vec<&Expr> = constraints.iter().collect();
+    let bindings = bindings(fields, &exprs);
+    quote! {
+        #bindings
+        ::testgen::CONS!(#(#exprs),*)
+    }
+}
+
+/// Binds every field mentioned in `exprs` to a local of the same name, so attributes can say
+/// `n` instead of `self.n`. Primitive fields are copied so `n > 0` compiles, others such as
+/// `Vec`s are borrowed.
+fn bindings(fields: &[Field], exprs: &[&Expr]) -> TokenStream2 {
+    let mut used = Vec::new();
+    for expr in exprs {
+        collect_idents(quote!(#expr), &mut used);
+    }
+    let bindings = fields
+        .iter()
+        .filter(|field| used.contains(&field.ident))
+        .map(|field| {
+            let ident = &field.ident;
+            if is_primitive(inner_type(&field.ty)) {
+                quote! { let #ident = self.#ident; }
+            } else {
+                quote! { let #ident = &self.#ident; }
+            }
+        });
+    quote! { #(#bindings)* }
+}
+
+fn collect_idents(tokens: TokenStream2, idents: &mut Vec<Ident>) {
+    let mut after_dot = false;
+    for token in tokens {
+        match &token {
+            TokenTree::Ident(ident) if !after_dot => idents.push(ident.clone()),
+            TokenTree::Group(group) => collect_idents(group.stream(), idents),
+            _ => {}
+        }
+        after_dot = matches!(&token, TokenTree::Punct(punct) if punct.as_char() == '.');
+    }
+}
+
+/// A reference to the field's value, unwrapping `Option` fields.
+fn access(field: &Field) -> TokenStream2 {
+    let ident = &field.ident;
+    if option_inner(&field.ty).is_some() {
+        let message = format!("`{}` is not set", ident);
+        quote! { self.#ident.as_ref().expect(#message) }
+    } else {
+        quote! { (&self.#ident) }
+    }
+}
+
+fn inner_type(ty: &Type) -> &Type {
+    option_inner(ty).unwrap_or(ty)
+}
+
+fn option_inner(ty: &Type) -> Option<&Type> {
+    generic_argument(ty, "Option")
+}
+
+fn is_primitive(ty: &Type) -> bool {
+    const PRIMITIVES: &[&str] = &[
+        "i8", "i16", "i32", "i64", "i128", "isize", "u8", "u16", "u32", "u64", "u128", "usize",
+        "f32", "f64", "bool", "char",
+    ];
+    match ty {
+        Type::Path(path) => path
+            .path
+            .get_ident()
+            .is_some_and(|ident| PRIMITIVES.iter().any(|primitive| ident == primitive)),
+        _ => false,
+    }
+}
+
+fn is_vec(ty: &Type) -> bool {
+    generic_argument(ty, "Vec").is_some()
+}
+
+fn generic_argument<'a>(ty: &'a Type, name: &str) -> Option<&'a Type> {
+    let segment = match ty {
+        Type::Path(path) => path.path.segments.last()?,
+        _ => return None,
+    };
+    if segment.ident != Ident::new(name, Span::call_site()) {
+        return None;
+    }
+    match &segment.arguments {
+        PathArguments::AngleBracketed(arguments) => match arguments.args.first()? {
+            GenericArgument::Type(ty) => Some(ty),
+            _ => None,
+        },
+        _ => None,
+    }
+}